@@ -2,15 +2,25 @@ use crate::config::CameraOptions;
 use crate::geometry::Geometry;
 use crate::geometry::HitRecord;
 use crate::geometry::Hittable;
+use crate::geometry::frame::Frame;
 use crate::interval::Interval;
+use crate::lights::Illuminate;
+use crate::lights::LightSource;
+use crate::material::Material;
 use crate::material::Surface;
+use crate::material::medium::Medium;
+use crate::math::henyey_greenstein;
 use crate::math::random_in_unit_disk;
+use crate::math::reflectance;
+use crate::math::sample_henyey_greenstein;
 use crate::ray::Ray;
+use crate::spectrum::Spectrum;
 use image::RgbImage;
 use itertools::iproduct;
 use nalgebra::Vector3;
 use rand::prelude::*;
 use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
 use std::io::{self, Write};
 use std::sync::Arc;
 use std::sync::mpsc::channel;
@@ -39,6 +49,7 @@ pub struct Camera {
     pub defocus_angle: f64,
 
     pub background: Vector3<f64>,
+    pub spectrum: Option<Spectrum>,
 }
 
 fn linear_to_gamma(linear_component: f64) -> f64 {
@@ -110,6 +121,7 @@ impl Camera {
 
         let max_bounces = options.max_bounces;
         let threads = options.threads;
+        let spectrum = options.spectral.then(Spectrum::new);
 
         Self {
             image_width,
@@ -127,22 +139,25 @@ impl Camera {
             background,
             sqrt_spp,
             recip_sqrt_spp,
+            spectrum,
         }
     }
 
-    pub fn render(&self, world: &Geometry) -> RgbImage {
+    pub fn render(&self, world: &Geometry, lights: &[LightSource]) -> RgbImage {
         let now = Instant::now();
 
         let pool = ThreadPool::new(self.threads);
         let (tx, rx) = channel();
         let world = Arc::new(world.clone());
+        let lights: Arc<[LightSource]> = lights.into();
         for y in 0..self.image_height {
             for x in 0..self.image_width {
                 let tx = tx.clone();
                 let camera = *self;
                 let world = Arc::clone(&world);
+                let lights = Arc::clone(&lights);
                 pool.execute(move || {
-                    tx.send((x, y, camera.get_pixel(&world, x, y)))
+                    tx.send((x, y, camera.get_pixel(&world, &lights, x, y)))
                         .expect("Failed to send result");
                 });
             }
@@ -188,16 +203,36 @@ impl Camera {
         image
     }
 
-    pub fn get_pixel(&self, world: &Geometry, x: u32, y: u32) -> image::Rgb<u8> {
+    pub fn get_pixel(
+        &self,
+        world: &Geometry,
+        lights: &[LightSource],
+        x: u32,
+        y: u32,
+    ) -> image::Rgb<u8> {
         let mut rng = rand::rng();
+
+        // Wavelengths are spread evenly over the samples, shuffled so they do not line up with
+        // where in the pixel each sample lands.
+        let count = self.sqrt_spp * self.sqrt_spp;
+        let mut strata: Vec<u32> = (0..count).collect();
+        if self.spectrum.is_some() {
+            strata.shuffle(&mut rng);
+        }
+
         let color: Vector3<f64> = iproduct!(0..self.sqrt_spp, 0..self.sqrt_spp)
-            .map(|(s_x, s_y)| {
-                self.ray_color(
-                    &self.get_ray(x, y, s_x, s_y, &mut rng),
-                    self.max_bounces,
-                    world,
-                    &mut rng,
-                )
+            .zip(strata)
+            .map(|((s_x, s_y), stratum)| {
+                let mut ray = self.get_ray(x, y, s_x, s_y, &mut rng);
+                if self.spectrum.is_some() {
+                    let wavelength = Spectrum::sample_wavelength(stratum, count, &mut rng);
+                    ray.wavelength = Some(wavelength);
+                }
+                let color = self.ray_color(&ray, self.max_bounces, world, lights, &mut rng);
+                match (self.spectrum, ray.wavelength) {
+                    (Some(spectrum), Some(wavelength)) => spectrum.color(color.x, wavelength),
+                    _ => color,
+                }
             })
             .sum::<Vector3<f64>>()
             * self.samples_scale;
@@ -225,16 +260,174 @@ impl Camera {
         Ray::new(ray_origin, ray_direction, rng.random::<f64>())
     }
 
+    /// Reduces a color to its value at the wavelength of a spectral ray.
+    fn at_wavelength(&self, color: Vector3<f64>, ray: &Ray) -> Vector3<f64> {
+        match (self.spectrum, ray.wavelength) {
+            (Some(spectrum), Some(wavelength)) => {
+                Vector3::from_element(spectrum.sample(&color, wavelength))
+            }
+            _ => color,
+        }
+    }
+
     pub fn defocus_disk_sample(&self, rng: &mut ThreadRng) -> Vector3<f64> {
         let p = random_in_unit_disk(rng);
         self.center + (p.x * self.defocus_disk_u) + (p.y * self.defocus_disk_v)
     }
 
+    /// Light reaching the hit straight from the lights of the scene, as reflected back along the
+    /// ray by its material. Anything in between casts a shadow.
+    fn direct_light(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        world: &Geometry,
+        lights: &[LightSource],
+        rng: &mut ThreadRng,
+    ) -> Vector3<f64> {
+        lights
+            .iter()
+            .map(|light| {
+                let incident = light.illuminate(&record.point);
+                let reflected = record.material.evaluate(ray, record, &incident.direction);
+                if reflected.max() <= 0.0 || incident.irradiance.max() <= 0.0 {
+                    return Vector3::default();
+                }
+
+                let shadow = Ray {
+                    origin: record.point,
+                    direction: incident.direction,
+                    ..*ray
+                };
+                let interval = Interval::new(0.001, incident.distance);
+                if world.hit(&shadow, &interval, &mut HitRecord::default(), rng) {
+                    return Vector3::default();
+                }
+
+                let transmittance = ray.media.transmittance(incident.distance);
+                self.at_wavelength(incident.irradiance, ray)
+                    .component_mul(&self.at_wavelength(transmittance, ray))
+                    .component_mul(&self.at_wavelength(reflected, ray))
+            })
+            .sum()
+    }
+
+    /// Light from the lights of the scene reaching a point inside a scattering `medium`, as
+    /// scattered back along the ray. Light only gets in through the surface bounding the medium,
+    /// where how it bends is ignored.
+    fn medium_light(
+        &self,
+        ray: &Ray,
+        point: &Vector3<f64>,
+        medium: &Medium,
+        world: &Geometry,
+        lights: &[LightSource],
+        rng: &mut ThreadRng,
+    ) -> Vector3<f64> {
+        lights
+            .iter()
+            .map(|light| {
+                let incident = light.illuminate(point);
+                if incident.irradiance.max() <= 0.0 {
+                    return Vector3::default();
+                }
+
+                let shadow = Ray {
+                    origin: *point,
+                    direction: incident.direction,
+                    ..*ray
+                };
+                let mut record = HitRecord::default();
+                let interval = Interval::new(0.001, incident.distance);
+                let transmittance = match world.hit(&shadow, &interval, &mut record, rng) {
+                    false => ray.media.transmittance(incident.distance),
+                    true => {
+                        let Material::Subsurface(subsurface) = &record.material else {
+                            return Vector3::default();
+                        };
+                        if record.front_face || !subsurface.fills(medium) {
+                            return Vector3::default();
+                        }
+
+                        let mut media = ray.media;
+                        media.remove(medium);
+                        let outside = media
+                            .current()
+                            .map_or(1.0, |outside| outside.refraction_index);
+                        let r_index = medium.refraction_index / outside;
+                        let cos_theta = f64::min(-record.normal.dot(&incident.direction), 1.0);
+                        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                        if r_index * sin_theta > 1.0 {
+                            return Vector3::default();
+                        }
+
+                        let outgoing = Ray {
+                            origin: record.point,
+                            media,
+                            ..shadow
+                        };
+                        let rest = incident.distance - record.t;
+                        let interval = Interval::new(0.001, rest);
+                        if world.hit(&outgoing, &interval, &mut HitRecord::default(), rng) {
+                            return Vector3::default();
+                        }
+
+                        ray.media
+                            .transmittance(record.t)
+                            .component_mul(&media.transmittance(rest))
+                            * (1.0 - reflectance(cos_theta, r_index))
+                    }
+                };
+
+                let cos_theta = ray.direction.normalize().dot(&incident.direction);
+                let phase = henyey_greenstein(medium.anisotropy, cos_theta);
+                self.at_wavelength(incident.irradiance, ray)
+                    .component_mul(&self.at_wavelength(transmittance, ray))
+                    * phase
+            })
+            .sum()
+    }
+
+    /// The light scattered back along the ray at `distance` along it inside a scattering medium,
+    /// from where a random walk carries on in a direction picked by the phase function.
+    fn scatter_in_medium(
+        &self,
+        ray: &Ray,
+        distance: f64,
+        depth: u32,
+        world: &Geometry,
+        lights: &[LightSource],
+        rng: &mut ThreadRng,
+    ) -> Vector3<f64> {
+        let Some(medium) = ray.media.current().copied() else {
+            return Vector3::default();
+        };
+        let point = ray.at(distance / ray.direction.norm());
+
+        let cos_theta = sample_henyey_greenstein(medium.anisotropy, rng);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * rng.random::<f64>();
+        let frame = Frame::new(point, ray.direction);
+        let scattered = Ray {
+            origin: point,
+            direction: frame.vector_to_world(&Vector3::new(
+                sin_theta * phi.cos(),
+                sin_theta * phi.sin(),
+                cos_theta,
+            )),
+            ..*ray
+        };
+
+        let color_from_lights = self.medium_light(ray, &point, &medium, world, lights, rng);
+        color_from_lights + self.ray_color(&scattered, depth - 1, world, lights, rng)
+    }
+
     pub fn ray_color(
         &self,
         ray: &Ray,
         depth: u32,
         world: &Geometry,
+        lights: &[LightSource],
         rng: &mut ThreadRng,
     ) -> Vector3<f64> {
         if depth == 0 {
@@ -245,26 +438,46 @@ impl Camera {
         let interval = Interval::new(0.001, f64::INFINITY);
 
         if !world.hit(ray, &interval, &mut hit_record, rng) {
-            return self.background;
+            let background = self
+                .background
+                .component_mul(&ray.media.transmittance(f64::INFINITY));
+            return self.at_wavelength(background, ray);
+        }
+
+        // Light reaching the hit is dimmed by whatever the ray travelled through to get there,
+        // unless the medium scatters it somewhere along the way.
+        let mut ray = *ray;
+        let (collision, transmittance) = ray.media.sample_scattering(
+            hit_record.t * ray.direction.norm(),
+            &mut ray.channel_pdfs,
+            rng,
+        );
+        let ray = &ray;
+        let transmittance = self.at_wavelength(transmittance, ray);
+        if let Some(distance) = collision {
+            return transmittance
+                .component_mul(&self.scatter_in_medium(ray, distance, depth, world, lights, rng));
         }
 
-        let mut scattered = Ray::default();
+        // Scattered rays stay inside the same media unless the material changes them.
+        let mut scattered = *ray;
         let mut attenuation = Vector3::<f64>::default();
         let color_from_emission =
-            hit_record
-                .material
-                .emitted(hit_record.u, hit_record.v, hit_record.point);
+            self.at_wavelength(hit_record.material.emitted(ray, &hit_record), ray);
+
+        let color_from_lights = self.direct_light(ray, &hit_record, world, lights, rng);
 
         if !hit_record
             .material
             .scatter(ray, &hit_record, &mut attenuation, &mut scattered, rng)
         {
-            return color_from_emission;
+            return transmittance.component_mul(&(color_from_emission + color_from_lights));
         }
 
-        let color_from_scatter =
-            attenuation.component_mul(&self.ray_color(&scattered, depth - 1, world, rng));
+        let color_from_scatter = self
+            .at_wavelength(attenuation, ray)
+            .component_mul(&self.ray_color(&scattered, depth - 1, world, lights, rng));
 
-        color_from_emission + color_from_scatter
+        transmittance.component_mul(&(color_from_emission + color_from_lights + color_from_scatter))
     }
 }
@@ -1,39 +1,106 @@
 use crate::geometry::Geometry;
 use crate::geometry::axis::Axis;
+use crate::geometry::bump::Bump;
+use crate::geometry::cone::Cone;
+use crate::geometry::csg::Csg;
+use crate::geometry::csg::Operation;
 use crate::geometry::cube::Cube;
+use crate::geometry::cylinder::Cylinder;
+use crate::geometry::disk::Disk;
+use crate::geometry::gltf::Gltf;
+use crate::geometry::gltf::scene_nodes;
+use crate::geometry::gltf::tex_coord_set;
+use crate::geometry::mesh::Face;
+use crate::geometry::mesh::Mesh;
+use crate::geometry::mesh::MeshData;
+use crate::geometry::opacity::Opacity;
+use crate::geometry::plane::Plane;
 use crate::geometry::quad::Quad;
 use crate::geometry::rotate::Rotate;
 use crate::geometry::scale::Scale;
+use crate::geometry::sdf::Distance;
+use crate::geometry::sdf::Sdf;
 use crate::geometry::sphere::Sphere;
+use crate::geometry::torus::Torus;
 use crate::geometry::translate::Translate;
 use crate::geometry::triangle::Triangle;
 use crate::geometry::triangle::Vertex;
+use crate::geometry::volume::Density;
+use crate::geometry::volume::Grid;
 use crate::geometry::volume::Volume;
-use crate::geometry::wavefront::Wavefront;
+use crate::geometry::wavefront;
+use crate::lights::LightSource;
+use crate::lights::directional::Directional;
+use crate::lights::point::Point;
+use crate::lights::spot::Spot;
 use crate::material::Material;
+use crate::material::anisotropic::Anisotropic;
+use crate::material::coat::Coat;
 use crate::material::dielectric::Dielectric;
+use crate::material::dielectric::Dispersion;
+use crate::material::isotropic::Isotropic;
 use crate::material::lambertian::Lambertian;
 use crate::material::light::Light;
 use crate::material::metal::Metal;
+use crate::material::mix::Mix;
+use crate::material::principled::Principled;
+use crate::material::subsurface::Subsurface;
+use crate::material::texture::Add;
+use crate::material::texture::Channel;
 use crate::material::texture::Checkered;
+use crate::material::texture::ColorRamp;
+use crate::material::texture::Colors;
+use crate::material::texture::Fbm;
+use crate::material::texture::Gradient;
 use crate::material::texture::Image;
+use crate::material::texture::Lerp;
+use crate::material::texture::Multiply;
 use crate::material::texture::Noise;
+use crate::material::texture::Remap;
+use crate::material::texture::Ridged;
 use crate::material::texture::SolidColor;
+use crate::material::texture::Texture;
+use crate::material::texture::Wood;
+use crate::material::texture::Worley;
+use crate::material::texture::WorleyFeature;
+use crate::material::thin_film::ThinFilm;
+use crate::math::near_zero;
+use crate::noise::NoiseMode;
+use crate::noise::Perlin;
+use crate::spectrum::blackbody;
 use clap::Parser;
 use colored::Colorize;
+use gltf::khr_lights_punctual::Kind;
+use image::DynamicImage;
+use image::ImageBuffer;
 use image::ImageReader;
+use image::Rgb32FImage;
+use nalgebra::Matrix4;
+use nalgebra::Point3;
+use nalgebra::Rotation3;
+use nalgebra::Vector2;
 use nalgebra::Vector3;
+use obj::raw::material::Material as MtlMaterial;
+use obj::raw::material::MtlColor;
+use obj::raw::material::parse_mtl;
 use obj::raw::object::Group;
 use obj::raw::object::RawObj;
 use obj::raw::object::parse_obj;
+use ply_rs::parser::Parser as PlyParser;
+use ply_rs::ply::DefaultElement;
+use ply_rs::ply::Property;
 use serde::Deserialize;
 use serde_inline_default::serde_inline_default;
+use std::collections::HashMap;
 use std::error::Error;
+use std::f64::consts::PI;
 use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
 use std::ops::Range;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -51,6 +118,16 @@ pub struct Args {
     pub samples: Option<u32>,
 }
 
+/// Directory of the configuration file, which other files in the configuration are relative to.
+fn config_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let args = Args::parse();
+    let config_dir = args
+        .config
+        .parent()
+        .ok_or("Failed to get parent directory")?;
+    Ok(config_dir.to_path_buf())
+}
+
 fn file_exists(path: &str) -> Result<PathBuf, String> {
     let path_buf = PathBuf::from(path);
     if path_buf.is_file() {
@@ -103,14 +180,12 @@ impl fmt::Display for AspectRatios {
     }
 }
 
-#[serde_inline_default]
-#[derive(Deserialize, Debug)]
+#[derive(Debug)]
 pub struct CameraOptions {
     pub aspect_ratio: AspectRatios,
     pub image_width: u32,
     pub samples: u32,
     pub max_bounces: u32,
-    #[serde(default = "default_threads")]
     pub threads: usize,
     pub fov: f64,
 
@@ -118,13 +193,139 @@ pub struct CameraOptions {
     pub look_at: [f64; 3],
     pub vup: [f64; 3],
 
-    #[serde_inline_default(0.0)]
     pub defocus_angle: f64,
-    #[serde_inline_default(1.0)]
     pub focus_dist: f64,
 
-    #[serde(default)]
     pub background: [f64; 3],
+    pub spectral: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawGltfCamera {
+    file: String,
+    scene: Option<String>,
+    name: Option<String>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+#[serde_inline_default]
+#[derive(Deserialize)]
+struct RawCameraOptions {
+    aspect_ratio: AspectRatios,
+    image_width: u32,
+    samples: u32,
+    max_bounces: u32,
+    #[serde(default = "default_threads")]
+    threads: usize,
+    fov: Option<f64>,
+
+    look_from: Option<[f64; 3]>,
+    look_at: Option<[f64; 3]>,
+    vup: Option<[f64; 3]>,
+
+    #[serde_inline_default(0.0)]
+    defocus_angle: f64,
+    #[serde_inline_default(1.0)]
+    focus_dist: f64,
+
+    #[serde(default)]
+    background: [f64; 3],
+    #[serde(default)]
+    spectral: bool,
+
+    gltf: Option<RawGltfCamera>,
+}
+
+impl RawCameraOptions {
+    fn into_camera_options(self) -> Result<CameraOptions, Box<dyn Error>> {
+        let (mut fov, mut look_from, mut look_at, mut vup) =
+            (self.fov, self.look_from, self.look_at, self.vup);
+
+        if let Some(gltf) = self.gltf {
+            let (document, _, _) = gltf::import(config_dir()?.join(gltf.file))?;
+            let scene = gltf_scene(&document, gltf.scene)?;
+            let camera = gltf_camera(
+                &scene,
+                gltf.name.as_deref(),
+                &transform_matrix(&gltf.transform),
+            )?;
+
+            fov = fov.or(Some(camera.fov));
+            look_from = look_from.or(Some(camera.look_from));
+            look_at = look_at.or(Some(camera.look_at));
+            vup = vup.or(Some(camera.vup));
+        }
+
+        Ok(CameraOptions {
+            aspect_ratio: self.aspect_ratio,
+            image_width: self.image_width,
+            samples: self.samples,
+            max_bounces: self.max_bounces,
+            threads: self.threads,
+            fov: fov.ok_or("missing field `fov`")?,
+            look_from: look_from.ok_or("missing field `look_from`")?,
+            look_at: look_at.ok_or("missing field `look_at`")?,
+            vup: vup.unwrap_or([0.0, 1.0, 0.0]),
+            defocus_angle: self.defocus_angle,
+            focus_dist: self.focus_dist,
+            background: self.background,
+            spectral: self.spectral,
+        })
+    }
+}
+
+/// Where a glTF camera is and what it looks at.
+struct GltfCamera {
+    fov: f64,
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    vup: [f64; 3],
+}
+
+/// The perspective camera of `scene` with the given `name`, or its first one, placed by its node
+/// hierarchy and then by `transform`.
+fn gltf_camera(
+    scene: &gltf::Scene,
+    name: Option<&str>,
+    transform: &Matrix4<f64>,
+) -> Result<GltfCamera, Box<dyn Error>> {
+    let (camera, local) = scene_nodes(scene)
+        .into_iter()
+        .filter_map(|(node, local)| node.camera().map(|camera| (camera, local)))
+        .find(|(camera, _)| name.is_none() || camera.name() == name)
+        .ok_or("Camera does not exist in the glTF scene")?;
+
+    let yfov = match camera.projection() {
+        gltf::camera::Projection::Perspective(perspective) => perspective.yfov() as f64,
+        gltf::camera::Projection::Orthographic(_) => {
+            return Err("Orthographic glTF cameras are not supported".into());
+        }
+    };
+
+    // glTF cameras look down their local -z axis with +y up.
+    let world = transform * local;
+    let origin = world.transform_point(&Point3::origin());
+    let target = world.transform_point(&Point3::new(0.0, 0.0, -1.0));
+    let up = world.transform_vector(&Vector3::y());
+    Ok(GltfCamera {
+        fov: yfov.to_degrees(),
+        look_from: origin.coords.into(),
+        look_at: target.coords.into(),
+        vup: up.into(),
+    })
+}
+
+impl<'de> Deserialize<'de> for CameraOptions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawCameraOptions::deserialize(deserializer)?
+            .into_camera_options()
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 fn default_threads() -> usize {
@@ -140,16 +341,47 @@ impl CameraOptions {
     }
 }
 
+#[derive(Deserialize)]
+enum MetalPreset {
+    #[serde(rename = "gold")]
+    Gold,
+    #[serde(rename = "silver")]
+    Silver,
+    #[serde(rename = "copper")]
+    Copper,
+    #[serde(rename = "aluminum")]
+    Aluminum,
+}
+
+impl MetalPreset {
+    /// The complex refraction index `eta + ik` of the metal for red, green and blue light.
+    fn refraction_index(&self) -> ([f64; 3], [f64; 3]) {
+        match self {
+            MetalPreset::Gold => ([0.143, 0.374, 1.442], [3.983, 2.385, 1.603]),
+            MetalPreset::Silver => ([0.155, 0.117, 0.138], [4.828, 3.122, 2.147]),
+            MetalPreset::Copper => ([0.200, 0.924, 1.102], [3.912, 2.452, 2.142]),
+            MetalPreset::Aluminum => ([1.657, 0.880, 0.521], [9.224, 6.270, 4.837]),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSellmeier {
+    b: [f64; 3],
+    c: [f64; 3],
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "material", deny_unknown_fields)]
 enum MaterialDef {
     #[serde(rename = "lambertian")]
-    Lambertian { albedo: [f64; 3] },
+    Lambertian { albedo: RawColor },
 
     #[serde(rename = "checkered")]
     Checkered {
-        even: Option<[f64; 3]>,
-        odd: Option<[f64; 3]>,
+        even: Option<Box<RawColor>>,
+        odd: Option<Box<RawColor>>,
         scale: Option<f64>,
     },
 
@@ -157,76 +389,795 @@ enum MaterialDef {
     Texture { file: String },
 
     #[serde(rename = "noise")]
-    Noise {
-        scale: Option<f64>,
-        turbulance: Option<u32>,
-    },
+    Noise(RawNoise),
 
     #[serde(rename = "metal")]
-    Metal { albedo: [f64; 3], roughness: f64 },
+    Metal {
+        albedo: Option<RawColor>,
+        preset: Option<MetalPreset>,
+        eta: Option<[f64; 3]>,
+        k: Option<[f64; 3]>,
+        roughness: f64,
+        film: Option<RawFilm>,
+    },
 
     #[serde(rename = "dielectric")]
-    Dielectric { refraction_index: f64 },
+    Dielectric {
+        refraction_index: Option<f64>,
+        cauchy: Option<[f64; 2]>,
+        sellmeier: Option<RawSellmeier>,
+        priority: Option<u32>,
+        absorption: Option<[f64; 3]>,
+        absorption_distance: Option<f64>,
+        roughness: Option<f64>,
+        film: Option<RawFilm>,
+    },
 
     #[serde(rename = "glass")]
-    Glass {},
+    Glass {
+        priority: Option<u32>,
+        absorption: Option<[f64; 3]>,
+        absorption_distance: Option<f64>,
+        roughness: Option<f64>,
+        film: Option<RawFilm>,
+    },
 
     #[serde(rename = "water")]
-    Water {},
+    Water {
+        priority: Option<u32>,
+        absorption: Option<[f64; 3]>,
+        absorption_distance: Option<f64>,
+        roughness: Option<f64>,
+        film: Option<RawFilm>,
+    },
 
     #[serde(rename = "light")]
-    Light { emit: [f64; 3] },
+    Light(RawLight),
+
+    #[serde(rename = "principled")]
+    Principled(Box<RawPrincipled>),
+
+    #[serde(rename = "mix")]
+    Mix {
+        a: Box<MaterialDef>,
+        b: Box<MaterialDef>,
+        factor: Option<RawScalar>,
+    },
+
+    #[serde(rename = "coat")]
+    Coat {
+        base: Box<MaterialDef>,
+        refraction_index: Option<f64>,
+        roughness: Option<f64>,
+        color: Option<RawColor>,
+    },
+
+    #[serde(rename = "subsurface")]
+    Subsurface {
+        color: Option<RawColor>,
+        scattering_radius: [f64; 3],
+        scale: Option<f64>,
+        anisotropy: Option<f64>,
+        refraction_index: Option<f64>,
+        roughness: Option<f64>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPrincipled {
+    base_color: Option<RawColor>,
+    metallic: Option<RawScalar>,
+    roughness: Option<RawScalar>,
+    specular: Option<RawScalar>,
+    clearcoat: Option<RawScalar>,
+    clearcoat_roughness: Option<RawScalar>,
+    sheen: Option<RawScalar>,
+    transmission: Option<RawScalar>,
+    refraction_index: Option<f64>,
+    emission: Option<RawColor>,
+}
+
+impl RawPrincipled {
+    fn into_material(self) -> Result<Material, Box<dyn Error>> {
+        let refraction_index = self.refraction_index.unwrap_or(1.5);
+        if refraction_index <= 0.0 {
+            return Err("The refraction_index of a principled material must be positive".into());
+        }
+        Ok(Material::Principled(Box::new(Principled {
+            base_color: RawColor::texture(self.base_color, [0.8; 3])?,
+            metallic: RawScalar::texture(self.metallic, 0.0)?,
+            roughness: RawScalar::texture(self.roughness, 0.5)?,
+            specular: RawScalar::texture(self.specular, 0.5)?,
+            clearcoat: RawScalar::texture(self.clearcoat, 0.0)?,
+            clearcoat_roughness: RawScalar::texture(self.clearcoat_roughness, 0.03)?,
+            sheen: RawScalar::texture(self.sheen, 0.0)?,
+            transmission: RawScalar::texture(self.transmission, 0.0)?,
+            refraction_index,
+            emission: RawColor::texture(self.emission, [0.0; 3])?,
+        })))
+    }
+}
+
+/// Lumens per watt of light at the wavelength the eye is most sensitive to.
+const LUMINOUS_EFFICACY: f64 = 683.0;
+
+/// The color of a black body at `kelvin`, or white without a temperature.
+fn kelvin_color(kelvin: Option<f64>) -> Result<Vector3<f64>, Box<dyn Error>> {
+    match kelvin {
+        Some(kelvin) if kelvin <= 0.0 => {
+            Err("The kelvin temperature of a light must be positive".into())
+        }
+        Some(kelvin) => Ok(blackbody(kelvin)),
+        None => Ok(Vector3::from_element(1.0)),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLight {
+    emit: Option<RawColor>,
+    kelvin: Option<f64>,
+    watts: Option<f64>,
+    lumens: Option<f64>,
+    two_sided: Option<bool>,
+}
+
+impl RawLight {
+    fn into_light(self, area: Option<f64>) -> Result<Material, Box<dyn Error>> {
+        if self.emit.is_none() && self.kelvin.is_none() {
+            return Err("A light needs an `emit` color or a `kelvin` temperature".into());
+        }
+        let two_sided = self.two_sided.unwrap_or(true);
+
+        let color = kelvin_color(self.kelvin)?;
+
+        // Power leaves the surface evenly in every direction of each emitting side, so radiance
+        // is the power divided by PI times the emitting area.
+        let watts = match (self.watts, self.lumens) {
+            (Some(_), Some(_)) => return Err("A light cannot use both watts and lumens".into()),
+            (Some(watts), None) => Some(watts),
+            (None, Some(lumens)) => Some(lumens / LUMINOUS_EFFICACY),
+            (None, None) => None,
+        };
+        let intensity = match (watts, area) {
+            (None, _) => color,
+            (Some(watts), Some(area)) => {
+                let sides = if two_sided { 2.0 } else { 1.0 };
+                color * watts / (sides * PI * area)
+            }
+            (Some(_), None) => {
+                return Err(
+                    "Lights in watts or lumens must be a sphere, quad, triangle, disk or cube"
+                        .into(),
+                );
+            }
+        };
+
+        Ok(Light::emitter(
+            RawColor::texture(self.emit, [1.0; 3])?,
+            intensity,
+            two_sided,
+        ))
+    }
+}
+
+/// A dielectric tinted to the `absorption` color after light travels `absorption_distance`
+/// through it.
+fn dielectric(
+    refraction_index: f64,
+    dispersion: Dispersion,
+    priority: Option<u32>,
+    absorption: Option<[f64; 3]>,
+    absorption_distance: Option<f64>,
+    roughness: Option<f64>,
+    film: Option<RawFilm>,
+) -> Result<Material, Box<dyn Error>> {
+    let distance = absorption_distance.unwrap_or(1.0);
+    if distance <= 0.0 {
+        return Err("The absorption_distance of a dielectric must be positive".into());
+    }
+    let absorption = Vector3::from(absorption.unwrap_or([1.0; 3]))
+        .map(|color| -color.clamp(1e-6, 1.0).ln() / distance);
+    Ok(Dielectric::medium(
+        refraction_index,
+        dispersion,
+        absorption,
+        priority.unwrap_or(0),
+        roughness.unwrap_or(0.0),
+        film.map(RawFilm::into_film).transpose()?,
+    ))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFilm {
+    thickness: f64,
+    refraction_index: Option<f64>,
+    thickness_map: Option<TextureDef>,
+}
+
+impl RawFilm {
+    fn into_film(self) -> Result<ThinFilm, Box<dyn Error>> {
+        if self.thickness < 0.0 {
+            return Err("The thickness of a film must not be negative".into());
+        }
+        let refraction_index = self.refraction_index.unwrap_or(1.33);
+        if refraction_index <= 0.0 {
+            return Err("The refraction_index of a film must be positive".into());
+        }
+        let texture = self
+            .thickness_map
+            .map(TextureDef::into_texture)
+            .transpose()?;
+        Ok(ThinFilm::new(self.thickness, texture, refraction_index))
+    }
 }
 
 impl MaterialDef {
     fn into_material(self) -> Result<Material, Box<dyn Error>> {
-        let args = Args::parse();
-        let config_dir = args
-            .config
-            .parent()
-            .ok_or("Failed to get parent directory")?;
+        self.into_material_with_area(None)
+    }
 
+    /// Builds the material of an object with a surface `area`, which lights given in power units
+    /// spread their power over.
+    fn into_material_with_area(self, area: Option<f64>) -> Result<Material, Box<dyn Error>> {
         match self {
-            MaterialDef::Lambertian { albedo } => Ok(Lambertian::material(SolidColor::texture(
-                Vector3::new(albedo[0], albedo[1], albedo[2]),
-            ))),
-            MaterialDef::Checkered { even, odd, scale } => {
-                let scale = scale.unwrap_or(1.0);
-                let even = even.unwrap_or([0.05, 0.05, 0.05]);
-                let odd = odd.unwrap_or([0.95, 0.95, 0.95]);
+            MaterialDef::Lambertian { albedo } => Ok(Lambertian::material(RawColor::texture(
+                Some(albedo),
+                [0.0; 3],
+            )?)),
+            MaterialDef::Checkered { even, odd, scale } => Ok(Lambertian::material(
+                TextureDef::Checkered { even, odd, scale }.into_texture()?,
+            )),
+            MaterialDef::Texture { file } => Ok(Lambertian::material(
+                TextureDef::Image { file }.into_texture()?,
+            )),
+            MaterialDef::Noise(noise) => Ok(Lambertian::material(
+                TextureDef::Noise(noise).into_texture()?,
+            )),
+            MaterialDef::Metal {
+                albedo,
+                preset,
+                eta,
+                k,
+                roughness,
+                film,
+            } => {
+                let film = film.map(RawFilm::into_film).transpose()?;
+                match (albedo, preset, eta, k) {
+                    (Some(albedo), None, None, None) => Ok(Metal::material(
+                        RawColor::texture(Some(albedo), [1.0; 3])?,
+                        roughness,
+                        film,
+                    )),
+                    (None, Some(preset), None, None) => {
+                        let (eta, k) = preset.refraction_index();
+                        Ok(Metal::conductor(
+                            Vector3::from(eta),
+                            Vector3::from(k),
+                            roughness,
+                            film,
+                        ))
+                    }
+                    (None, None, Some(eta), Some(k)) => Ok(Metal::conductor(
+                        Vector3::from(eta),
+                        Vector3::from(k),
+                        roughness,
+                        film,
+                    )),
+                    _ => Err(
+                        "A metal needs either an `albedo`, a `preset`, or both `eta` and `k`"
+                            .into(),
+                    ),
+                }
+            }
+            MaterialDef::Dielectric {
+                refraction_index,
+                cauchy,
+                sellmeier,
+                priority,
+                absorption,
+                absorption_distance,
+                roughness,
+                film,
+            } => {
+                let dispersion = match (cauchy, sellmeier) {
+                    (None, None) => Dispersion::None,
+                    (Some([a, b]), None) => Dispersion::Cauchy { a, b },
+                    (None, Some(RawSellmeier { b, c })) => Dispersion::Sellmeier { b, c },
+                    (Some(_), Some(_)) => {
+                        return Err("A dielectric cannot use both cauchy and sellmeier".into());
+                    }
+                };
+                // Without an explicit index, the one of yellow sodium light is used.
+                let refraction_index = refraction_index
+                    .or(dispersion.refraction_index(589.3))
+                    .ok_or("missing field `refraction_index`")?;
+                dielectric(
+                    refraction_index,
+                    dispersion,
+                    priority,
+                    absorption,
+                    absorption_distance,
+                    roughness,
+                    film,
+                )
+            }
+            MaterialDef::Glass {
+                priority,
+                absorption,
+                absorption_distance,
+                roughness,
+                film,
+            } => dielectric(
+                1.5,
+                Dispersion::None,
+                priority,
+                absorption,
+                absorption_distance,
+                roughness,
+                film,
+            ),
+            MaterialDef::Water {
+                priority,
+                absorption,
+                absorption_distance,
+                roughness,
+                film,
+            } => dielectric(
+                1.33,
+                Dispersion::None,
+                priority,
+                absorption,
+                absorption_distance,
+                roughness,
+                film,
+            ),
+            MaterialDef::Light(light) => light.into_light(area),
+            MaterialDef::Principled(principled) => principled.into_material(),
+            MaterialDef::Mix { a, b, factor } => Ok(Mix::material(
+                a.into_material_with_area(area)?,
+                b.into_material_with_area(area)?,
+                RawScalar::texture(factor, 0.5)?,
+            )),
+            MaterialDef::Coat {
+                base,
+                refraction_index,
+                roughness,
+                color,
+            } => {
+                let refraction_index = refraction_index.unwrap_or(1.5);
+                if refraction_index <= 0.0 {
+                    return Err("The refraction_index of a coat must be positive".into());
+                }
+                Ok(Coat::material(
+                    base.into_material_with_area(area)?,
+                    refraction_index,
+                    roughness.unwrap_or(0.0),
+                    RawColor::texture(color, [1.0; 3])?,
+                ))
+            }
+            MaterialDef::Subsurface {
+                color,
+                scattering_radius,
+                scale,
+                anisotropy,
+                refraction_index,
+                roughness,
+            } => {
+                let radius = Vector3::from(scattering_radius) * scale.unwrap_or(1.0);
+                if radius.min() <= 0.0 {
+                    return Err(
+                        "The scattering_radius of a subsurface material must be positive".into(),
+                    );
+                }
+                let anisotropy = anisotropy.unwrap_or(0.0);
+                if anisotropy.abs() >= 1.0 {
+                    return Err(
+                        "The anisotropy of a subsurface material must be between -1 and 1".into(),
+                    );
+                }
+                let refraction_index = refraction_index.unwrap_or(1.4);
+                if refraction_index <= 0.0 {
+                    return Err(
+                        "The refraction_index of a subsurface material must be positive".into(),
+                    );
+                }
+                Ok(Subsurface::material(
+                    RawColor::texture(color, [0.8; 3])?,
+                    radius,
+                    anisotropy,
+                    refraction_index,
+                    roughness.unwrap_or(0.0),
+                ))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "texture", deny_unknown_fields)]
+enum TextureDef {
+    #[serde(rename = "checkered")]
+    Checkered {
+        even: Option<Box<RawColor>>,
+        odd: Option<Box<RawColor>>,
+        scale: Option<f64>,
+    },
+
+    #[serde(rename = "image")]
+    Image { file: String },
+
+    #[serde(rename = "noise", alias = "marble")]
+    Noise(RawNoise),
+
+    #[serde(rename = "wood")]
+    Wood {
+        scale: Option<f64>,
+        distortion: Option<f64>,
+        low: Option<[f64; 3]>,
+        high: Option<[f64; 3]>,
+        seed: Option<u64>,
+    },
+
+    #[serde(rename = "worley")]
+    Worley {
+        scale: Option<f64>,
+        feature: Option<RawWorleyFeature>,
+        low: Option<[f64; 3]>,
+        high: Option<[f64; 3]>,
+        seed: Option<u64>,
+    },
+
+    #[serde(rename = "fbm")]
+    Fbm {
+        scale: Option<f64>,
+        octaves: Option<u32>,
+        lacunarity: Option<f64>,
+        gain: Option<f64>,
+        low: Option<[f64; 3]>,
+        high: Option<[f64; 3]>,
+        seed: Option<u64>,
+    },
+
+    #[serde(rename = "ridged")]
+    Ridged {
+        scale: Option<f64>,
+        octaves: Option<u32>,
+        lacunarity: Option<f64>,
+        gain: Option<f64>,
+        offset: Option<f64>,
+        low: Option<[f64; 3]>,
+        high: Option<[f64; 3]>,
+        seed: Option<u64>,
+    },
+
+    #[serde(rename = "gradient")]
+    Gradient {
+        start: [f64; 3],
+        end: [f64; 3],
+        low: Option<[f64; 3]>,
+        high: Option<[f64; 3]>,
+    },
+
+    #[serde(rename = "color_ramp")]
+    ColorRamp {
+        input: Box<TextureDef>,
+        stops: Vec<RawStop>,
+    },
+
+    #[serde(rename = "multiply")]
+    Multiply { a: Box<RawColor>, b: Box<RawColor> },
+
+    #[serde(rename = "add")]
+    Add { a: Box<RawColor>, b: Box<RawColor> },
+
+    #[serde(rename = "lerp")]
+    Lerp {
+        a: Box<RawColor>,
+        b: Box<RawColor>,
+        factor: Box<RawScalar>,
+    },
+
+    #[serde(rename = "remap")]
+    Remap {
+        input: Box<TextureDef>,
+        from: Option<[f64; 2]>,
+        to: Option<[f64; 2]>,
+    },
+}
+
+/// Perlin noise, as a texture, the color of a lambertian material or the density of a volume.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawNoise {
+    scale: Option<f64>,
+    mode: Option<RawNoiseMode>,
+    #[serde(alias = "turbulance")]
+    octaves: Option<u32>,
+    lacunarity: Option<f64>,
+    gain: Option<f64>,
+    low: Option<[f64; 3]>,
+    high: Option<[f64; 3]>,
+    seed: Option<u64>,
+}
+
+impl RawNoise {
+    /// The noise, shaped by `mode` unless the config picks another one.
+    fn into_noise(self, mode: NoiseMode) -> Result<Noise, Box<dyn Error>> {
+        let mode = match self.mode {
+            None => mode,
+            Some(RawNoiseMode::Raw) => NoiseMode::Raw,
+            Some(RawNoiseMode::Turbulence) => NoiseMode::Turbulence,
+            Some(RawNoiseMode::Marble) => NoiseMode::Marble,
+            Some(RawNoiseMode::SineWarped) => NoiseMode::SineWarped,
+        };
+        let (octaves, lacunarity, gain) =
+            fractal(Some(self.octaves.unwrap_or(7)), self.lacunarity, self.gain)?;
+        Ok(Noise::new(
+            perlin(self.seed),
+            self.scale.unwrap_or(1.0),
+            mode,
+            octaves,
+            lacunarity,
+            gain,
+            pattern_colors(self.low, self.high),
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+enum RawNoiseMode {
+    #[serde(rename = "raw")]
+    Raw,
+    #[serde(rename = "turbulence")]
+    Turbulence,
+    #[serde(rename = "marble")]
+    Marble,
+    #[serde(rename = "sine_warped")]
+    SineWarped,
+}
+
+#[derive(Deserialize)]
+enum RawWorleyFeature {
+    #[serde(rename = "closest")]
+    Closest,
+    #[serde(rename = "second_closest")]
+    SecondClosest,
+    #[serde(rename = "edge")]
+    Edge,
+}
 
-                let even_color = Vector3::new(even[0], even[1], even[2]);
-                let odd_color = Vector3::new(odd[0], odd[1], odd[2]);
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawStop {
+    position: f64,
+    color: [f64; 3],
+}
+
+/// The colors of a pattern, black where it is 0 and white where it is 1 unless given.
+fn pattern_colors(low: Option<[f64; 3]>, high: Option<[f64; 3]>) -> Colors {
+    Colors {
+        low: Vector3::from(low.unwrap_or([0.0; 3])),
+        high: Vector3::from(high.unwrap_or([1.0; 3])),
+    }
+}
+
+/// Perlin noise following the `seed`, or a random one when not given.
+fn perlin(seed: Option<u64>) -> Perlin {
+    seed.map_or_else(Perlin::default, Perlin::new)
+}
 
-                let checkered = Checkered::texture(scale, even_color, odd_color);
-                Ok(Lambertian::material(checkered))
+/// The settings shared by fractal noise, checked for sense.
+fn fractal(
+    octaves: Option<u32>,
+    lacunarity: Option<f64>,
+    gain: Option<f64>,
+) -> Result<(u32, f64, f64), Box<dyn Error>> {
+    let octaves = octaves.unwrap_or(6);
+    if octaves == 0 {
+        return Err("Fractal noise needs at least one octave".into());
+    }
+    let lacunarity = lacunarity.unwrap_or(2.0);
+    if lacunarity <= 0.0 {
+        return Err("The lacunarity of fractal noise must be positive".into());
+    }
+    Ok((octaves, lacunarity, gain.unwrap_or(0.5)))
+}
+
+impl TextureDef {
+    fn into_texture(self) -> Result<Texture, Box<dyn Error>> {
+        match self {
+            TextureDef::Checkered { even, odd, scale } => {
+                let scale = scale.unwrap_or(1.0);
+                let even = RawColor::texture(even.map(|even| *even), [0.05; 3])?;
+                let odd = RawColor::texture(odd.map(|odd| *odd), [0.95; 3])?;
+                Ok(Checkered::texture(scale, even, odd))
             }
-            MaterialDef::Texture { file } => {
-                let texture_path = config_dir.join(file);
+            TextureDef::Image { file } => {
+                let texture_path = config_dir()?.join(file);
                 let buffer = ImageReader::open(texture_path)?.decode()?.to_rgb32f();
-                let image = Image::texture(buffer);
-                Ok(Lambertian::material(image))
+                Ok(Image::texture(buffer))
             }
-            MaterialDef::Noise { scale, turbulance } => {
-                let scale = scale.unwrap_or(1.0);
-                let turbulance = turbulance.unwrap_or(1);
-                Ok(Lambertian::material(Noise::texture(scale, turbulance)))
+            TextureDef::Noise(noise) => Ok(Texture::Noise(noise.into_noise(NoiseMode::Marble)?)),
+            TextureDef::Wood {
+                scale,
+                distortion,
+                low,
+                high,
+                seed,
+            } => Ok(Wood::texture(
+                perlin(seed),
+                scale.unwrap_or(1.0),
+                distortion.unwrap_or(0.5),
+                pattern_colors(low, high),
+            )),
+            TextureDef::Worley {
+                scale,
+                feature,
+                low,
+                high,
+                seed,
+            } => {
+                let feature = match feature.unwrap_or(RawWorleyFeature::Closest) {
+                    RawWorleyFeature::Closest => WorleyFeature::Closest,
+                    RawWorleyFeature::SecondClosest => WorleyFeature::SecondClosest,
+                    RawWorleyFeature::Edge => WorleyFeature::Edge,
+                };
+                Ok(Worley::texture(
+                    seed.unwrap_or_else(rand::random),
+                    scale.unwrap_or(1.0),
+                    feature,
+                    pattern_colors(low, high),
+                ))
             }
-            MaterialDef::Metal { albedo, roughness } => {
-                Ok(Metal::material(Vector3::from(albedo), roughness))
+            TextureDef::Fbm {
+                scale,
+                octaves,
+                lacunarity,
+                gain,
+                low,
+                high,
+                seed,
+            } => {
+                let (octaves, lacunarity, gain) = fractal(octaves, lacunarity, gain)?;
+                Ok(Fbm::texture(
+                    perlin(seed),
+                    scale.unwrap_or(1.0),
+                    octaves,
+                    lacunarity,
+                    gain,
+                    pattern_colors(low, high),
+                ))
             }
-            MaterialDef::Dielectric { refraction_index } => {
-                Ok(Dielectric::material(refraction_index))
+            TextureDef::Ridged {
+                scale,
+                octaves,
+                lacunarity,
+                gain,
+                offset,
+                low,
+                high,
+                seed,
+            } => {
+                let (octaves, lacunarity, _) = fractal(octaves, lacunarity, None)?;
+                Ok(Ridged::texture(
+                    perlin(seed),
+                    scale.unwrap_or(1.0),
+                    octaves,
+                    lacunarity,
+                    gain.unwrap_or(2.0),
+                    offset.unwrap_or(1.0),
+                    pattern_colors(low, high),
+                ))
             }
-            MaterialDef::Glass {} => Ok(Dielectric::material(1.5)),
-            MaterialDef::Water {} => Ok(Dielectric::material(1.33)),
-            MaterialDef::Light { emit } => {
-                Ok(Light::material(SolidColor::texture(Vector3::from(emit))))
+            TextureDef::Gradient {
+                start,
+                end,
+                low,
+                high,
+            } => Ok(Gradient::texture(
+                Vector3::from(start),
+                Vector3::from(end),
+                pattern_colors(low, high),
+            )),
+            TextureDef::ColorRamp { input, stops } => {
+                if stops.is_empty() {
+                    return Err("A color_ramp needs at least one stop".into());
+                }
+                let stops = stops
+                    .into_iter()
+                    .map(|stop| (stop.position, Vector3::from(stop.color)))
+                    .collect();
+                Ok(ColorRamp::texture(input.into_texture()?, stops))
             }
+            TextureDef::Multiply { a, b } => Ok(Multiply::texture(
+                RawColor::texture(Some(*a), [1.0; 3])?,
+                RawColor::texture(Some(*b), [1.0; 3])?,
+            )),
+            TextureDef::Add { a, b } => Ok(Add::texture(
+                RawColor::texture(Some(*a), [0.0; 3])?,
+                RawColor::texture(Some(*b), [0.0; 3])?,
+            )),
+            TextureDef::Lerp { a, b, factor } => Ok(Lerp::texture(
+                RawColor::texture(Some(*a), [0.0; 3])?,
+                RawColor::texture(Some(*b), [1.0; 3])?,
+                RawScalar::texture(Some(*factor), 0.5)?,
+            )),
+            TextureDef::Remap { input, from, to } => Ok(Remap::texture(
+                input.into_texture()?,
+                from.unwrap_or([0.0, 1.0]),
+                to.unwrap_or([0.0, 1.0]),
+            )),
         }
     }
 }
 
+/// A color given either directly or as a texture.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawColor {
+    Constant([f64; 3]),
+    Texture(TextureDef),
+}
+
+impl RawColor {
+    fn texture(color: Option<RawColor>, default: [f64; 3]) -> Result<Texture, Box<dyn Error>> {
+        match color.unwrap_or(RawColor::Constant(default)) {
+            RawColor::Constant(color) => Ok(SolidColor::texture(Vector3::from(color))),
+            RawColor::Texture(texture_def) => texture_def.into_texture(),
+        }
+    }
+}
+
+/// A single value given either directly or as a texture, whose channels are averaged.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawScalar {
+    Constant(f64),
+    Texture(TextureDef),
+}
+
+impl RawScalar {
+    fn texture(value: Option<RawScalar>, default: f64) -> Result<Texture, Box<dyn Error>> {
+        match value.unwrap_or(RawScalar::Constant(default)) {
+            RawScalar::Constant(value) => Ok(SolidColor::texture(Vector3::from_element(value))),
+            RawScalar::Texture(texture_def) => texture_def.into_texture(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawBump {
+    strength: f64,
+    #[serde(flatten)]
+    texture_def: TextureDef,
+}
+
+impl RawBump {
+    fn apply(self, geometry: Geometry) -> Result<Geometry, Box<dyn Error>> {
+        let texture = self.texture_def.into_texture()?;
+        Ok(Bump::geometry(geometry, texture, self.strength))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawOpacity {
+    threshold: Option<f64>,
+    #[serde(flatten)]
+    texture_def: TextureDef,
+}
+
+impl RawOpacity {
+    fn into_opacity(self) -> Result<Opacity, Box<dyn Error>> {
+        Ok(Opacity::new(
+            self.texture_def.into_texture()?,
+            self.threshold,
+        ))
+    }
+}
+
 #[derive(Deserialize)]
 struct RawTranslate {
     offset: [f64; 3],
@@ -266,25 +1217,187 @@ impl Transform {
             Transform::Scale(trans) => Scale::geometry(geometry, Vector3::from(trans.scalar)),
         }
     }
+
+    /// The same transform as a matrix, for placing what is not geometry, like lights.
+    fn matrix(&self) -> Matrix4<f64> {
+        match self {
+            Transform::Translate(trans) => Matrix4::new_translation(&Vector3::from(trans.offset)),
+            Transform::Rotate(trans) => {
+                let axis = match trans.axis {
+                    Axis::X => Vector3::x_axis(),
+                    Axis::Y => Vector3::y_axis(),
+                    Axis::Z => Vector3::z_axis(),
+                };
+                Rotation3::from_axis_angle(&axis, trans.degrees.to_radians()).to_homogeneous()
+            }
+            Transform::Scale(trans) => {
+                Matrix4::new_nonuniform_scaling(&Vector3::from(trans.scalar))
+            }
+        }
+    }
+}
+
+/// The matrix of a list of `transforms` applied in order.
+fn transform_matrix(transforms: &[Transform]) -> Matrix4<f64> {
+    transforms
+        .iter()
+        .fold(Matrix4::identity(), |matrix, transform| {
+            transform.matrix() * matrix
+        })
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+enum RawDensity {
+    #[serde(rename = "noise")]
+    Noise(RawNoise),
+
+    #[serde(rename = "grid")]
+    Grid { file: String },
+}
+
+impl RawDensity {
+    fn into_density(self) -> Result<Density, Box<dyn Error>> {
+        match self {
+            RawDensity::Noise(noise) => {
+                Ok(Density::Noise(noise.into_noise(NoiseMode::Turbulence)?))
+            }
+            RawDensity::Grid { file } => {
+                let mut grid_raw = BufReader::new(File::open(config_dir()?.join(file))?);
+                Ok(Density::Grid(Grid::read(&mut grid_raw)?))
+            }
+        }
+    }
 }
 
 #[derive(Deserialize)]
 struct RawVolume {
     density: f64,
-    albedo: [f64; 3],
+    albedo: RawColor,
+    field: Option<RawDensity>,
+    emission: Option<RawColor>,
+    g: Option<f64>,
+    back_g: Option<f64>,
+    back_weight: Option<f64>,
 }
 
-#[derive(Deserialize)]
-struct RawSphere {
-    #[serde(rename = "position")]
-    center: [f64; 3],
-    direction: Option<[f64; 3]>,
-    radius: f64,
-    #[serde(flatten)]
-    material_def: MaterialDef,
-    #[serde(default)]
-    transform: Vec<Transform>,
-    volume: Option<RawVolume>,
+impl RawVolume {
+    fn apply(self, geometry: Geometry) -> Result<Geometry, Box<dyn Error>> {
+        let field = match self.field {
+            Some(field) => field.into_density()?,
+            None => Density::Constant,
+        };
+
+        let phase_function = phase_function(
+            self.albedo,
+            self.emission,
+            (self.g, self.back_g, self.back_weight),
+        )?;
+        Ok(Volume::geometry(
+            geometry,
+            self.density,
+            field,
+            phase_function,
+        ))
+    }
+}
+
+/// Scatters evenly unless any of the asymmetry `lobes` is given.
+fn phase_function(
+    albedo: RawColor,
+    emission: Option<RawColor>,
+    lobes: (Option<f64>, Option<f64>, Option<f64>),
+) -> Result<Material, Box<dyn Error>> {
+    let texture = RawColor::texture(Some(albedo), [1.0; 3])?;
+    let emission = RawColor::texture(emission, [0.0; 3])?;
+    match lobes {
+        (None, None, None) => Ok(Isotropic::material(texture, emission)),
+        (g, back_g, back_weight) => {
+            let g = g.unwrap_or(0.0);
+            let back_g = back_g.unwrap_or(0.0);
+            let back_weight = back_weight.unwrap_or(0.0);
+            if g.abs() >= 1.0 || back_g.abs() >= 1.0 {
+                return Err("The asymmetry of a medium must be between -1 and 1".into());
+            }
+            if !(0.0..=1.0).contains(&back_weight) {
+                return Err("The back_weight of a medium must be between 0 and 1".into());
+            }
+            Ok(Anisotropic::material(
+                texture,
+                emission,
+                g,
+                back_g,
+                back_weight,
+            ))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AtmosphereOptions {
+    pub density: f64,
+    pub falloff: f64,
+    pub height: f64,
+    pub phase_function: Material,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAtmosphereOptions {
+    density: f64,
+    albedo: RawColor,
+    falloff: Option<f64>,
+    height: Option<f64>,
+    g: Option<f64>,
+    back_g: Option<f64>,
+    back_weight: Option<f64>,
+}
+
+impl RawAtmosphereOptions {
+    fn into_atmosphere_options(self) -> Result<AtmosphereOptions, Box<dyn Error>> {
+        if self.density < 0.0 {
+            return Err("The density of the atmosphere cannot be negative".into());
+        }
+        let falloff = self.falloff.unwrap_or(0.0);
+        if falloff < 0.0 {
+            return Err("The falloff of the atmosphere cannot be negative".into());
+        }
+        Ok(AtmosphereOptions {
+            density: self.density,
+            falloff,
+            height: self.height.unwrap_or(0.0),
+            phase_function: phase_function(
+                self.albedo,
+                None,
+                (self.g, self.back_g, self.back_weight),
+            )?,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for AtmosphereOptions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawAtmosphereOptions::deserialize(deserializer)?
+            .into_atmosphere_options()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSphere {
+    #[serde(rename = "position")]
+    center: [f64; 3],
+    direction: Option<[f64; 3]>,
+    radius: f64,
+    #[serde(flatten)]
+    material_def: MaterialDef,
+    bump: Option<RawBump>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+    volume: Option<RawVolume>,
 }
 
 impl RawSphere {
@@ -294,47 +1407,929 @@ impl RawSphere {
             None => Vector3::default(),
             Some(direction) => Vector3::from(direction),
         };
-        let material = self.material_def.into_material()?;
+        let area = 4.0 * PI * self.radius * self.radius;
+        let material = self.material_def.into_material_with_area(Some(area))?;
         let geometry = Sphere::geometry(center, direction, self.radius, material);
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+
+        let geometry = match self.volume {
+            Some(volume) => volume.apply(geometry)?,
+            None => geometry,
+        };
+
+        let geometry = self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom));
+
+        Ok(geometry)
+    }
+}
+
+#[derive(Deserialize)]
+struct RawQuad {
+    position: [f64; 3],
+    u: [f64; 3],
+    v: [f64; 3],
+    #[serde(flatten)]
+    material_def: MaterialDef,
+    bump: Option<RawBump>,
+    opacity: Option<RawOpacity>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+impl RawQuad {
+    fn into_quad(self) -> Result<Geometry, Box<dyn Error>> {
+        let area = Vector3::from(self.u).cross(&Vector3::from(self.v)).norm();
+        let material = self.material_def.into_material_with_area(Some(area))?;
+        let geometry = Quad::geometry(
+            Vector3::from(self.position),
+            Vector3::from(self.u),
+            Vector3::from(self.v),
+            material,
+            self.opacity.map(RawOpacity::into_opacity).transpose()?,
+        );
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+        Ok(self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom)))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawTriangle {
+    a: [f64; 3],
+    b: [f64; 3],
+    c: [f64; 3],
+
+    #[serde(flatten)]
+    material_def: MaterialDef,
+    bump: Option<RawBump>,
+    opacity: Option<RawOpacity>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+impl RawTriangle {
+    fn into_triangle(self) -> Result<Geometry, Box<dyn Error>> {
+        let [a, b, c] = [self.a, self.b, self.c].map(Vector3::from);
+        let area = (b - a).cross(&(c - a)).norm() / 2.0;
+        let material = self.material_def.into_material_with_area(Some(area))?;
+        let geometry = Triangle::geometry(
+            Vertex::new(a, None, None),
+            Vertex::new(b, None, None),
+            Vertex::new(c, None, None),
+            material,
+            self.opacity.map(RawOpacity::into_opacity).transpose()?,
+        );
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+        Ok(self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom)))
+    }
+}
+
+/// Reads an optional flattened material, only treating it as absent when no material keys were
+/// given at all so that a malformed definition is still reported.
+fn optional_material_def<'de, D>(deserializer: D) -> Result<Option<MaterialDef>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let table = toml::Table::deserialize(deserializer)?;
+    if table.is_empty() {
+        return Ok(None);
+    }
+
+    MaterialDef::deserialize(toml::Value::Table(table))
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn mtl_color(color: &Option<MtlColor>) -> Result<Option<Vector3<f64>>, Box<dyn Error>> {
+    match color {
+        Some(MtlColor::Rgb(r, g, b)) => Ok(Some(Vector3::new(*r as f64, *g as f64, *b as f64))),
+        Some(MtlColor::Xyz(x, y, z)) => {
+            let (x, y, z) = (*x as f64, *y as f64, *z as f64);
+            Ok(Some(Vector3::new(
+                3.2406 * x - 1.5372 * y - 0.4986 * z,
+                -0.9689 * x + 1.8758 * y + 0.0415 * z,
+                0.0557 * x - 0.2040 * y + 1.0570 * z,
+            )))
+        }
+        Some(MtlColor::Spectral(file, _)) => {
+            Err(format!("Spectral colors are not supported ({})", file).into())
+        }
+        None => Ok(None),
+    }
+}
+
+/// Maps a material from a `.mtl` library onto the closest material this renderer supports,
+/// resolving texture maps relative to `directory`.
+fn mtl_material(material: &MtlMaterial, directory: &Path) -> Result<Material, Box<dyn Error>> {
+    let diffuse = mtl_color(&material.diffuse)?.unwrap_or(Vector3::from_element(0.8));
+    let specular = mtl_color(&material.specular)?.unwrap_or_default();
+    let emissive = mtl_color(&material.emissive)?.unwrap_or_default();
+
+    if emissive.max() > 0.0 {
+        return Ok(Light::material(SolidColor::texture(emissive)));
+    }
+
+    if material.dissolve.unwrap_or(1.0) < 1.0 {
+        let refraction_index = material.optical_density.unwrap_or(1.5) as f64;
+        return Ok(Dielectric::material(refraction_index));
+    }
+
+    if material.diffuse_map.is_none() && specular.max() > diffuse.max() {
+        // Convert the Phong exponent into a width using the Blinn-Phong to Beckmann mapping,
+        // which is the square of the roughness.
+        let exponent = material.specular_exponent.unwrap_or(0.0) as f64;
+        let roughness = f64::min((2.0 / (exponent + 2.0)).sqrt(), 1.0).sqrt();
+        return Ok(Metal::material(
+            SolidColor::texture(specular),
+            roughness,
+            None,
+        ));
+    }
+
+    match &material.diffuse_map {
+        Some(map) => {
+            let buffer = ImageReader::open(directory.join(&map.file))?
+                .decode()?
+                .to_rgb32f();
+            Ok(Lambertian::material(Image::texture(buffer)))
+        }
+        None => Ok(Lambertian::material(SolidColor::texture(diffuse))),
+    }
+}
+
+/// Applies the shading options shared by mesh objects, generating smooth normals when a
+/// `crease_angle` is given, loading the tangent-space `normal_map` and cutting out the `opacity`
+/// mask.
+fn shade_mesh(
+    mut data: MeshData,
+    crease_angle: Option<f64>,
+    normal_map: Option<String>,
+    opacity: Option<RawOpacity>,
+) -> Result<MeshData, Box<dyn Error>> {
+    data.opacity = opacity.map(RawOpacity::into_opacity).transpose()?;
+    if let Some(crease_angle) = crease_angle {
+        data.smooth_normals(crease_angle);
+    }
+
+    if let Some(file) = normal_map {
+        let buffer = ImageReader::open(config_dir()?.join(file))?
+            .decode()?
+            .to_rgb32f();
+        data.normal_map = Some(Image {
+            data: Arc::new(buffer),
+        });
+    }
+
+    Ok(data)
+}
+
+#[derive(Deserialize)]
+struct RawWavefront {
+    file: String,
+    group: Option<String>,
+    crease_angle: Option<f64>,
+    normal_map: Option<String>,
+
+    #[serde(flatten, deserialize_with = "optional_material_def")]
+    material_def: Option<MaterialDef>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDef>,
+    bump: Option<RawBump>,
+    opacity: Option<RawOpacity>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+impl RawWavefront {
+    /// Loads the file relative to `config_dir`.
+    fn into_wavefront(self, config_dir: &Path) -> Result<Geometry, Box<dyn Error>> {
+        let wavefront_path = config_dir.join(self.file);
+        let wavefront_dir = wavefront_path
+            .parent()
+            .ok_or("Failed to get parent directory")?
+            .to_path_buf();
+        let wavefront_raw = BufReader::new(File::open(&wavefront_path)?);
+        let object: RawObj = parse_obj(wavefront_raw)?;
+
+        let group = match self.group {
+            Some(group) => match object.groups.get(&group) {
+                Some(group) => Ok::<Option<&Group>, Box<dyn Error>>(Some(group)),
+                None => {
+                    return Err(format!(
+                        "Group {} does not exist, only found {:?}",
+                        group,
+                        object.groups.keys()
+                    )
+                    .into());
+                }
+            },
+            _ => Ok(None),
+        }?;
+
+        let mut library = HashMap::new();
+        if self.material_def.is_none() {
+            for file in object.material_libraries.iter() {
+                let mtl_raw = BufReader::new(File::open(wavefront_dir.join(file))?);
+                library.extend(parse_mtl(mtl_raw)?.materials);
+            }
+        }
+
+        let default_material = self
+            .material_def
+            .map(|material_def| material_def.into_material())
+            .transpose()?;
+        let mut overrides = self.materials;
+        if let Some(name) = overrides
+            .keys()
+            .find(|name| !object.meshes.contains_key(*name))
+        {
+            return Err(format!(
+                "Material {} is not used by any face, only found {:?}",
+                name,
+                object.meshes.keys()
+            )
+            .into());
+        }
+
+        // Only the materials of the faces kept are loaded, so those of other groups, which may
+        // point at textures that are not there, cannot fail the scene.
+        let mut materials = HashMap::new();
+        for (name, mesh) in object.meshes.iter() {
+            let used = match group {
+                Some(group) => mesh.polygons.iter().any(|range| {
+                    group
+                        .polygons
+                        .iter()
+                        .any(|kept| range.start < kept.end && kept.start < range.end)
+                }),
+                None => true,
+            };
+            if !used {
+                continue;
+            }
+
+            let material = match (overrides.remove(name), &default_material, library.get(name)) {
+                (Some(material_def), _, _) => material_def.into_material()?,
+                (None, Some(material), _) => material.clone(),
+                (None, None, Some(material)) => mtl_material(material, &wavefront_dir)?,
+                (None, None, None) if name.is_empty() => {
+                    return Err("Faces without a usemtl statement need a material".into());
+                }
+                (None, None, None) => {
+                    return Err(format!("Material {} was not found in any mtllib", name).into());
+                }
+            };
+            materials.insert(name.clone(), material);
+        }
+
+        let data = wavefront::mesh_data(&object, group, &materials);
+        let geometry = Mesh::geometry(shade_mesh(
+            data,
+            self.crease_angle,
+            self.normal_map,
+            self.opacity,
+        )?);
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+        Ok(self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom)))
+    }
+}
+
+fn gltf_scene<'a>(
+    document: &'a gltf::Document,
+    name: Option<String>,
+) -> Result<gltf::Scene<'a>, Box<dyn Error>> {
+    match name {
+        Some(name) => document
+            .scenes()
+            .find(|scene| scene.name() == Some(name.as_str()))
+            .ok_or_else(|| format!("Scene {} does not exist in the glTF file", name).into()),
+        None => document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .ok_or_else(|| "The glTF file does not contain a scene".into()),
+    }
+}
+
+fn gltf_image(
+    texture: Option<gltf::Texture>,
+    images: &[gltf::image::Data],
+) -> Result<Option<Rgb32FImage>, Box<dyn Error>> {
+    let Some(texture) = texture else {
+        return Ok(None);
+    };
+
+    let data = &images[texture.source().index()];
+    let pixels = data.pixels.clone();
+    let image = match data.format {
+        gltf::image::Format::R8 => {
+            ImageBuffer::from_raw(data.width, data.height, pixels).map(DynamicImage::ImageLuma8)
+        }
+        gltf::image::Format::R8G8 => {
+            ImageBuffer::from_raw(data.width, data.height, pixels).map(DynamicImage::ImageLumaA8)
+        }
+        gltf::image::Format::R8G8B8 => {
+            ImageBuffer::from_raw(data.width, data.height, pixels).map(DynamicImage::ImageRgb8)
+        }
+        gltf::image::Format::R8G8B8A8 => {
+            ImageBuffer::from_raw(data.width, data.height, pixels).map(DynamicImage::ImageRgba8)
+        }
+        format => return Err(format!("Unsupported glTF image format {:?}", format).into()),
+    }
+    .ok_or("Failed to read glTF image")?;
+
+    Ok(Some(image.to_rgb32f()))
+}
+
+/// The color `factor` of a glTF material, multiplied by its texture when it has one.
+fn gltf_color(
+    texture: Option<gltf::texture::Info>,
+    factor: Vector3<f64>,
+    images: &[gltf::image::Data],
+) -> Result<Texture, Box<dyn Error>> {
+    let factor = SolidColor::texture(factor);
+    Ok(
+        match gltf_image(texture.map(|info| info.texture()), images)? {
+            Some(image) => Multiply::texture(Image::texture(image), factor),
+            None => factor,
+        },
+    )
+}
+
+/// Maps a glTF metallic-roughness material onto a principled material. Metallic and roughness
+/// are read from the blue and green channels of the metallic-roughness texture, scaled by their
+/// factors.
+fn gltf_material(
+    material: &gltf::Material,
+    images: &[gltf::image::Data],
+) -> Result<Material, Box<dyn Error>> {
+    tex_coord_set(material)?;
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor().map(|c| c as f64);
+    let base_color = gltf_color(pbr.base_color_texture(), Vector3::new(r, g, b), images)?;
+
+    let emissive = Vector3::from(material.emissive_factor().map(|c| c as f64))
+        * material.emissive_strength().unwrap_or(1.0) as f64;
+    let emission = gltf_color(material.emissive_texture(), emissive, images)?;
+
+    let transmission = material.transmission().map_or(0.0, |transmission| {
+        transmission.transmission_factor() as f64
+    });
+    let constant = |value: f64| SolidColor::texture(Vector3::from_element(value));
+
+    let metallic_roughness = gltf_image(
+        pbr.metallic_roughness_texture().map(|info| info.texture()),
+        images,
+    )?
+    .map(Arc::new);
+    let packed = |channel: usize, factor: f32| match &metallic_roughness {
+        Some(image) => Multiply::texture(
+            Channel::texture(image.clone(), channel),
+            constant(factor as f64),
+        ),
+        None => constant(factor as f64),
+    };
+
+    Ok(Material::Principled(Box::new(Principled {
+        base_color,
+        metallic: packed(2, pbr.metallic_factor()),
+        roughness: packed(1, pbr.roughness_factor()),
+        specular: constant(0.5),
+        clearcoat: constant(0.0),
+        clearcoat_roughness: constant(0.03),
+        sheen: constant(0.0),
+        transmission: constant(transmission),
+        refraction_index: material.ior().unwrap_or(1.5) as f64,
+        emission,
+    })))
+}
+
+/// The KHR_lights_punctual lights of `scene`, placed by its node hierarchy and then by
+/// `transform`. Their photometric intensities are converted like `lumens` and `lux`. The `range`
+/// of a light is ignored, as lights here fall off with distance alone.
+fn gltf_lights(scene: &gltf::Scene, transform: &Matrix4<f64>) -> Vec<LightSource> {
+    scene_nodes(scene)
+        .into_iter()
+        .filter_map(|(node, local)| {
+            let light = node.light()?;
+            let world = transform * local;
+            // Lights shine down the -z axis of their node.
+            let position = world.transform_point(&Point3::origin()).coords;
+            let direction = world.transform_vector(&-Vector3::z());
+            // A node scaled flat along z leaves its light pointing nowhere.
+            if near_zero(&direction) && !matches!(light.kind(), Kind::Point) {
+                return None;
+            }
+            let color = Vector3::from(light.color().map(|c| c as f64));
+            let power = color * light.intensity() as f64 / LUMINOUS_EFFICACY;
+            Some(match light.kind() {
+                Kind::Point => Point::light(position, power),
+                Kind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => Spot::light(
+                    position,
+                    direction,
+                    power,
+                    (inner_cone_angle as f64).to_degrees(),
+                    (outer_cone_angle as f64).to_degrees(),
+                ),
+                Kind::Directional => Directional::light(direction, power),
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct RawGltf {
+    file: String,
+    scene: Option<String>,
+    #[serde(default)]
+    lights: bool,
+
+    #[serde(flatten, deserialize_with = "optional_material_def")]
+    material_def: Option<MaterialDef>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDef>,
+    bump: Option<RawBump>,
+    opacity: Option<RawOpacity>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+impl RawGltf {
+    /// Builds the meshes of the file, along with its lights when they are imported.
+    fn into_gltf(self) -> Result<(Geometry, Vec<LightSource>), Box<dyn Error>> {
+        let (document, buffers, images) = gltf::import(config_dir()?.join(self.file))?;
+        let scene = gltf_scene(&document, self.scene)?;
+
+        let default_material = self
+            .material_def
+            .map(|material_def| material_def.into_material())
+            .transpose()?;
+        let mut overrides = self
+            .materials
+            .into_iter()
+            .map(|(name, material_def)| Ok((name, material_def.into_material()?)))
+            .collect::<Result<HashMap<String, Material>, Box<dyn Error>>>()?;
+
+        let mut materials = Vec::new();
+        for material in document.materials() {
+            let name = material.name().unwrap_or_default();
+            materials.push(match (overrides.remove(name), &default_material) {
+                (Some(material), _) => material,
+                (None, Some(material)) => material.clone(),
+                (None, None) => gltf_material(&material, &images)?,
+            });
+        }
+
+        if let Some(name) = overrides.keys().next() {
+            return Err(format!(
+                "Material {} does not exist in the glTF file, only found {:?}",
+                name,
+                document
+                    .materials()
+                    .filter_map(|material| material.name())
+                    .collect::<Vec<&str>>()
+            )
+            .into());
+        }
+
+        // Primitives without a material use the glTF default, a white non-metal.
+        let default = match default_material {
+            Some(material) => material,
+            None => Lambertian::material(SolidColor::texture(Vector3::from_element(1.0))),
+        };
+        let lights = match self.lights {
+            true => gltf_lights(&scene, &transform_matrix(&self.transform)),
+            false => Vec::new(),
+        };
+
+        let opacity = self.opacity.map(RawOpacity::into_opacity).transpose()?;
+        let geometry = Gltf::geometry(&scene, &buffers, &materials, &default, opacity);
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+        let geometry = self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom));
+        Ok((geometry, lights))
+    }
+}
+
+fn ply_scalar(element: &DefaultElement, keys: &[&str]) -> Option<f64> {
+    keys.iter()
+        .find_map(|key| element.get(*key))
+        .and_then(|property| match property {
+            Property::Char(value) => Some(*value as f64),
+            Property::UChar(value) => Some(*value as f64),
+            Property::Short(value) => Some(*value as f64),
+            Property::UShort(value) => Some(*value as f64),
+            Property::Int(value) => Some(*value as f64),
+            Property::UInt(value) => Some(*value as f64),
+            Property::Float(value) => Some(*value as f64),
+            Property::Double(value) => Some(*value),
+            _ => None,
+        })
+}
+
+fn ply_vector(element: &DefaultElement, keys: [&[&str]; 3]) -> Option<Vector3<f64>> {
+    Some(Vector3::new(
+        ply_scalar(element, keys[0])?,
+        ply_scalar(element, keys[1])?,
+        ply_scalar(element, keys[2])?,
+    ))
+}
+
+fn ply_color(element: &DefaultElement) -> Option<Vector3<f64>> {
+    let channel = |keys: [&str; 3]| match keys.iter().find_map(|key| element.get(*key))? {
+        Property::UChar(value) => Some(*value as f64 / 255.0),
+        Property::Float(value) => Some(*value as f64),
+        Property::Double(value) => Some(*value),
+        _ => None,
+    };
+
+    // Colors are stored gamma encoded, and squared into linear light like those of images.
+    let color = Vector3::new(
+        channel(["red", "r", "diffuse_red"])?,
+        channel(["green", "g", "diffuse_green"])?,
+        channel(["blue", "b", "diffuse_blue"])?,
+    );
+    Some(color.map(|c| c * c))
+}
+
+fn ply_indices(element: &DefaultElement) -> Option<Vec<usize>> {
+    ["vertex_indices", "vertex_index"]
+        .iter()
+        .find_map(|key| element.get(*key))
+        .and_then(|property| match property {
+            Property::ListChar(list) => Some(list.iter().map(|i| *i as usize).collect()),
+            Property::ListUChar(list) => Some(list.iter().map(|i| *i as usize).collect()),
+            Property::ListShort(list) => Some(list.iter().map(|i| *i as usize).collect()),
+            Property::ListUShort(list) => Some(list.iter().map(|i| *i as usize).collect()),
+            Property::ListInt(list) => Some(list.iter().map(|i| *i as usize).collect()),
+            Property::ListUInt(list) => Some(list.iter().map(|i| *i as usize).collect()),
+            _ => None,
+        })
+}
+
+#[derive(Deserialize)]
+struct RawPly {
+    file: String,
+    crease_angle: Option<f64>,
+    normal_map: Option<String>,
+
+    #[serde(flatten, deserialize_with = "optional_material_def")]
+    material_def: Option<MaterialDef>,
+    bump: Option<RawBump>,
+    opacity: Option<RawOpacity>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+impl RawPly {
+    /// Loads the file relative to `config_dir`.
+    fn into_ply(self, config_dir: &Path) -> Result<Geometry, Box<dyn Error>> {
+        let mut ply_raw = BufReader::new(File::open(config_dir.join(self.file))?);
+        let ply = PlyParser::<DefaultElement>::new().read_ply(&mut ply_raw)?;
+
+        let no_elements = Vec::new();
+        let vertex_elements = ply.payload.get("vertex").unwrap_or(&no_elements);
+        let face_elements = ply.payload.get("face").unwrap_or(&no_elements);
+
+        let mut data = MeshData::default();
+        for element in vertex_elements.iter() {
+            let position = ply_vector(element, [&["x"], &["y"], &["z"]])
+                .ok_or("PLY vertex is missing its x, y or z position")?;
+            data.positions.push(position);
+            if let Some(normal) = ply_vector(element, [&["nx"], &["ny"], &["nz"]]) {
+                data.normals.push(normal);
+            }
+            if let (Some(u), Some(v)) = (
+                ply_scalar(element, &["u", "s", "texture_u", "texture_s"]),
+                ply_scalar(element, &["v", "t", "texture_v", "texture_t"]),
+            ) {
+                // PLY places v = 0 at the bottom of the image, textures sample from the top.
+                data.uvs.push(Vector2::new(u, 1.0 - v));
+            }
+        }
+        let has_normals = data.normals.len() == data.positions.len();
+        let has_uvs = data.uvs.len() == data.positions.len();
+
+        for element in face_elements.iter() {
+            let indices = ply_indices(element).ok_or("PLY face is missing its vertex indices")?;
+            if let Some(index) = indices.iter().find(|index| **index >= data.positions.len()) {
+                return Err(format!("PLY face references missing vertex {}", index).into());
+            }
+            for i in 1..indices.len().saturating_sub(1) {
+                let corners = [indices[0], indices[i], indices[i + 1]].map(|i| i as u32);
+                data.faces.push(Face {
+                    positions: corners,
+                    normals: has_normals.then_some(corners),
+                    uvs: has_uvs.then_some(corners),
+                    material: 0,
+                });
+            }
+        }
+
+        let colors = vertex_elements
+            .iter()
+            .map(ply_color)
+            .collect::<Option<Vec<Vector3<f64>>>>();
+
+        match (self.material_def, colors) {
+            (Some(material_def), _) => data.materials.push(material_def.into_material()?),
+            (None, Some(colors)) if !colors.is_empty() => data.colors = colors,
+            (None, _) => return Err("PLY files without vertex colors need a material".into()),
+        };
+
+        let geometry = Mesh::geometry(shade_mesh(
+            data,
+            self.crease_angle,
+            self.normal_map,
+            self.opacity,
+        )?);
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+        Ok(self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom)))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawStl {
+    file: String,
+    crease_angle: Option<f64>,
+
+    #[serde(flatten)]
+    material_def: MaterialDef,
+    bump: Option<RawBump>,
+    opacity: Option<RawOpacity>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+impl RawStl {
+    /// Loads the file relative to `config_dir`.
+    fn into_stl(self, config_dir: &Path) -> Result<Geometry, Box<dyn Error>> {
+        let mut stl_raw = BufReader::new(File::open(config_dir.join(self.file))?);
+        let stl = stl_io::read_stl(&mut stl_raw)?;
+
+        // STL only stores face normals, so triangles are shaded flat unless smoothed.
+        let data = MeshData {
+            positions: stl
+                .vertices
+                .iter()
+                .map(|vertex| Vector3::from(vertex.0.map(|c| c as f64)))
+                .collect(),
+            faces: stl
+                .faces
+                .iter()
+                .map(|face| Face {
+                    positions: face.vertices.map(|i| i as u32),
+                    normals: None,
+                    uvs: None,
+                    material: 0,
+                })
+                .collect(),
+            materials: vec![self.material_def.into_material()?],
+            ..MeshData::default()
+        };
+
+        let geometry = Mesh::geometry(shade_mesh(data, self.crease_angle, None, self.opacity)?);
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+        Ok(self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom)))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawCube {
+    a: [f64; 3],
+    b: [f64; 3],
+    #[serde(flatten)]
+    material_def: MaterialDef,
+    bump: Option<RawBump>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+    volume: Option<RawVolume>,
+}
+
+impl RawCube {
+    fn into_cube(self) -> Result<Geometry, Box<dyn Error>> {
+        let size = (Vector3::from(self.a) - Vector3::from(self.b)).abs();
+        let area = 2.0 * (size.x * size.y + size.y * size.z + size.z * size.x);
+        let material = self.material_def.into_material_with_area(Some(area))?;
+        let geometry = Cube::geometry(Vector3::from(self.a), Vector3::from(self.b), material);
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+
+        let geometry = match self.volume {
+            Some(volume) => volume.apply(geometry)?,
+            None => geometry,
+        };
+
+        let geometry = self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom));
+
+        Ok(geometry)
+    }
+}
+
+#[derive(Deserialize)]
+struct RawDisk {
+    position: [f64; 3],
+    normal: [f64; 3],
+    radius: f64,
+
+    #[serde(flatten)]
+    material_def: MaterialDef,
+    bump: Option<RawBump>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+impl RawDisk {
+    fn into_disk(self) -> Result<Geometry, Box<dyn Error>> {
+        if near_zero(&Vector3::from(self.normal)) {
+            return Err("The normal of a disk cannot be zero".into());
+        }
+        if self.radius <= 0.0 {
+            return Err("The radius of a disk must be positive".into());
+        }
+        let area = PI * self.radius * self.radius;
+        let material = self.material_def.into_material_with_area(Some(area))?;
+        let geometry = Disk::geometry(
+            Vector3::from(self.position),
+            Vector3::from(self.normal),
+            self.radius,
+            material,
+        );
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+        Ok(self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom)))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawCylinder {
+    a: [f64; 3],
+    b: [f64; 3],
+    radius: f64,
+    capped: Option<bool>,
+
+    #[serde(flatten)]
+    material_def: MaterialDef,
+    bump: Option<RawBump>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
+
+impl RawCylinder {
+    fn into_cylinder(self) -> Result<Geometry, Box<dyn Error>> {
+        if near_zero(&(Vector3::from(self.b) - Vector3::from(self.a))) {
+            return Err("The a and b of a cylinder cannot be the same point".into());
+        }
+        if self.radius <= 0.0 {
+            return Err("The radius of a cylinder must be positive".into());
+        }
+        let material = self.material_def.into_material()?;
+        let geometry = Cylinder::geometry(
+            Vector3::from(self.a),
+            Vector3::from(self.b),
+            self.radius,
+            self.capped.unwrap_or(true),
+            material,
+        );
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
+        Ok(self
+            .transform
+            .into_iter()
+            .fold(geometry, |geom, transform| transform.apply(geom)))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawCone {
+    a: [f64; 3],
+    b: [f64; 3],
+    radius: f64,
+    capped: Option<bool>,
+
+    #[serde(flatten)]
+    material_def: MaterialDef,
+    bump: Option<RawBump>,
+    #[serde(default)]
+    transform: Vec<Transform>,
+}
 
-        let geometry = match self.volume {
-            Some(volume) => Volume::geometry(
-                geometry,
-                volume.density,
-                SolidColor::texture(Vector3::from(volume.albedo)),
-            ),
+impl RawCone {
+    fn into_cone(self) -> Result<Geometry, Box<dyn Error>> {
+        if near_zero(&(Vector3::from(self.b) - Vector3::from(self.a))) {
+            return Err("The a and b of a cone cannot be the same point".into());
+        }
+        if self.radius <= 0.0 {
+            return Err("The radius of a cone must be positive".into());
+        }
+        let material = self.material_def.into_material()?;
+        let geometry = Cone::geometry(
+            Vector3::from(self.a),
+            Vector3::from(self.b),
+            self.radius,
+            self.capped.unwrap_or(true),
+            material,
+        );
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
             None => geometry,
         };
-
-        let geometry = self
+        Ok(self
             .transform
             .into_iter()
-            .fold(geometry, |geom, transform| transform.apply(geom));
-
-        Ok(geometry)
+            .fold(geometry, |geom, transform| transform.apply(geom)))
     }
 }
 
 #[derive(Deserialize)]
-struct RawQuad {
+struct RawTorus {
     position: [f64; 3],
-    u: [f64; 3],
-    v: [f64; 3],
+    axis: Option<[f64; 3]>,
+    major_radius: f64,
+    minor_radius: f64,
+
     #[serde(flatten)]
     material_def: MaterialDef,
+    bump: Option<RawBump>,
     #[serde(default)]
     transform: Vec<Transform>,
 }
 
-impl RawQuad {
-    fn into_quad(self) -> Result<Geometry, Box<dyn Error>> {
+impl RawTorus {
+    fn into_torus(self) -> Result<Geometry, Box<dyn Error>> {
+        let axis = Vector3::from(self.axis.unwrap_or([0.0, 1.0, 0.0]));
+        if near_zero(&axis) {
+            return Err("The axis of a torus cannot be zero".into());
+        }
+        if self.major_radius <= 0.0 || self.minor_radius <= 0.0 {
+            return Err("The major_radius and minor_radius of a torus must be positive".into());
+        }
         let material = self.material_def.into_material()?;
-        let geometry = Quad::geometry(
+        let geometry = Torus::geometry(
             Vector3::from(self.position),
-            Vector3::from(self.u),
-            Vector3::from(self.v),
+            axis,
+            self.major_radius,
+            self.minor_radius,
             material,
         );
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
         Ok(self
             .transform
             .into_iter()
@@ -343,26 +2338,32 @@ impl RawQuad {
 }
 
 #[derive(Deserialize)]
-struct RawTriangle {
-    a: [f64; 3],
-    b: [f64; 3],
-    c: [f64; 3],
+struct RawPlane {
+    position: [f64; 3],
+    normal: [f64; 3],
 
     #[serde(flatten)]
     material_def: MaterialDef,
+    bump: Option<RawBump>,
     #[serde(default)]
     transform: Vec<Transform>,
 }
 
-impl RawTriangle {
-    fn into_triangle(self) -> Result<Geometry, Box<dyn Error>> {
+impl RawPlane {
+    fn into_plane(self) -> Result<Geometry, Box<dyn Error>> {
+        if near_zero(&Vector3::from(self.normal)) {
+            return Err("The normal of a plane cannot be zero".into());
+        }
         let material = self.material_def.into_material()?;
-        let geometry = Triangle::geometry(
-            Vertex::new(Vector3::from(self.a), None),
-            Vertex::new(Vector3::from(self.b), None),
-            Vertex::new(Vector3::from(self.c), None),
+        let geometry = Plane::geometry(
+            Vector3::from(self.position),
+            Vector3::from(self.normal),
             material,
         );
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
         Ok(self
             .transform
             .into_iter()
@@ -371,46 +2372,30 @@ impl RawTriangle {
 }
 
 #[derive(Deserialize)]
-struct RawWavefront {
-    file: String,
-    group: Option<String>,
-
-    #[serde(flatten)]
-    material_def: MaterialDef,
+struct RawCsg {
+    objects: Vec<Geometry>,
+    bump: Option<RawBump>,
     #[serde(default)]
     transform: Vec<Transform>,
 }
 
-impl RawWavefront {
-    fn into_wavefront(self) -> Result<Geometry, Box<dyn Error>> {
-        let args = Args::parse();
-        let config_dir = args
-            .config
-            .parent()
-            .ok_or("Failed to get parent directory")?;
-
-        let wavefront_path = config_dir.join(self.file);
-        let wavefront_raw = BufReader::new(File::open(wavefront_path)?);
-        let object: RawObj = parse_obj(wavefront_raw)?;
-
-        let material = self.material_def.into_material()?;
-
-        let group = match self.group {
-            Some(group) => match object.groups.get(&group) {
-                Some(group) => Ok::<Option<&Group>, Box<dyn Error>>(Some(group)),
-                None => {
-                    return Err(format!(
-                        "Group {} does not exist, only found {:?}",
-                        group,
-                        object.groups.keys()
-                    )
-                    .into());
-                }
-            },
-            _ => Ok(None),
-        }?;
+impl RawCsg {
+    /// Combines the objects from first to last, so a difference removes every later object from
+    /// the first.
+    fn into_csg(self, operation: Operation) -> Result<Geometry, Box<dyn Error>> {
+        let mut objects = self.objects.into_iter();
+        let (Some(first), Some(second)) = (objects.next(), objects.next()) else {
+            return Err("Combining objects needs at least two objects".into());
+        };
+        let geometry = objects.fold(
+            Csg::geometry(operation.clone(), first, second),
+            |combined, object| Csg::geometry(operation.clone(), combined, object),
+        );
 
-        let geometry = Wavefront::geometry(&object, group, material);
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
+            None => geometry,
+        };
         Ok(self
             .transform
             .into_iter()
@@ -419,36 +2404,156 @@ impl RawWavefront {
 }
 
 #[derive(Deserialize)]
-struct RawCube {
-    a: [f64; 3],
-    b: [f64; 3],
+#[serde(tag = "type", deny_unknown_fields)]
+enum RawDistance {
+    #[serde(rename = "sphere")]
+    Sphere {
+        position: Option<[f64; 3]>,
+        radius: f64,
+    },
+
+    #[serde(rename = "box")]
+    Box {
+        position: Option<[f64; 3]>,
+        size: [f64; 3],
+    },
+
+    #[serde(rename = "round_box")]
+    RoundBox {
+        position: Option<[f64; 3]>,
+        size: [f64; 3],
+        radius: f64,
+    },
+
+    #[serde(rename = "torus")]
+    Torus {
+        position: Option<[f64; 3]>,
+        major_radius: f64,
+        minor_radius: f64,
+    },
+
+    #[serde(rename = "smooth_union")]
+    SmoothUnion {
+        a: Box<RawDistance>,
+        b: Box<RawDistance>,
+        smoothness: f64,
+    },
+
+    #[serde(rename = "smooth_subtract")]
+    SmoothSubtract {
+        a: Box<RawDistance>,
+        b: Box<RawDistance>,
+        smoothness: f64,
+    },
+
+    #[serde(rename = "repeat")]
+    Repeat {
+        shape: Box<RawDistance>,
+        period: [f64; 3],
+        count: [u32; 3],
+    },
+
+    #[serde(rename = "twist")]
+    Twist {
+        shape: Box<RawDistance>,
+        degrees: f64,
+    },
+}
+
+impl RawDistance {
+    fn into_distance(self) -> Result<Distance, Box<dyn Error>> {
+        let center = |position: Option<[f64; 3]>| Vector3::from(position.unwrap_or_default());
+
+        match self {
+            RawDistance::Sphere { position, radius } => Ok(Distance::Sphere {
+                center: center(position),
+                radius,
+            }),
+            RawDistance::Box { position, size } => Ok(Distance::Box {
+                center: center(position),
+                half_size: Vector3::from(size) / 2.0,
+                radius: 0.0,
+            }),
+            RawDistance::RoundBox {
+                position,
+                size,
+                radius,
+            } => {
+                let half_size = Vector3::from(size) / 2.0;
+                if radius > half_size.min() {
+                    return Err("The radius of a round box cannot exceed half its size".into());
+                }
+                Ok(Distance::Box {
+                    center: center(position),
+                    half_size,
+                    radius,
+                })
+            }
+            RawDistance::Torus {
+                position,
+                major_radius,
+                minor_radius,
+            } => Ok(Distance::Torus {
+                center: center(position),
+                major_radius,
+                minor_radius,
+            }),
+            RawDistance::SmoothUnion { a, b, smoothness } => Ok(Distance::SmoothUnion {
+                a: Box::new(a.into_distance()?),
+                b: Box::new(b.into_distance()?),
+                smoothness,
+            }),
+            RawDistance::SmoothSubtract { a, b, smoothness } => Ok(Distance::SmoothSubtract {
+                a: Box::new(a.into_distance()?),
+                b: Box::new(b.into_distance()?),
+                smoothness,
+            }),
+            RawDistance::Repeat {
+                shape,
+                period,
+                count,
+            } => {
+                if period.iter().any(|period| *period <= 0.0) {
+                    return Err("The period of a repeat must be positive along every axis".into());
+                }
+                Ok(Distance::Repeat {
+                    shape: Box::new(shape.into_distance()?),
+                    period: Vector3::from(period),
+                    count: Vector3::from(count.map(|count| count as f64)),
+                })
+            }
+            RawDistance::Twist { shape, degrees } => Ok(Distance::Twist {
+                shape: Box::new(shape.into_distance()?),
+                rate: degrees.to_radians(),
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSdf {
+    sdf: RawDistance,
+
     #[serde(flatten)]
     material_def: MaterialDef,
+    bump: Option<RawBump>,
     #[serde(default)]
     transform: Vec<Transform>,
-    volume: Option<RawVolume>,
 }
 
-impl RawCube {
-    fn into_cube(self) -> Result<Geometry, Box<dyn Error>> {
+impl RawSdf {
+    fn into_sdf(self) -> Result<Geometry, Box<dyn Error>> {
         let material = self.material_def.into_material()?;
-        let geometry = Cube::geometry(Vector3::from(self.a), Vector3::from(self.b), material);
+        let geometry = Sdf::geometry(self.sdf.into_distance()?, material);
 
-        let geometry = match self.volume {
-            Some(volume) => Volume::geometry(
-                geometry,
-                volume.density,
-                SolidColor::texture(Vector3::from(volume.albedo)),
-            ),
+        let geometry = match self.bump {
+            Some(bump) => bump.apply(geometry)?,
             None => geometry,
         };
-
-        let geometry = self
+        Ok(self
             .transform
             .into_iter()
-            .fold(geometry, |geom, transform| transform.apply(geom));
-
-        Ok(geometry)
+            .fold(geometry, |geom, transform| transform.apply(geom)))
     }
 }
 
@@ -465,6 +2570,56 @@ enum ObjectDef {
     Triangle(RawTriangle),
     #[serde(rename = "wavefront")]
     Wavefront(RawWavefront),
+    #[serde(rename = "gltf")]
+    Gltf(RawGltf),
+    #[serde(rename = "ply")]
+    Ply(RawPly),
+    #[serde(rename = "stl")]
+    Stl(RawStl),
+    #[serde(rename = "disk")]
+    Disk(RawDisk),
+    #[serde(rename = "cylinder")]
+    Cylinder(RawCylinder),
+    #[serde(rename = "cone")]
+    Cone(RawCone),
+    #[serde(rename = "torus")]
+    Torus(RawTorus),
+    #[serde(rename = "plane")]
+    Plane(RawPlane),
+    #[serde(rename = "union")]
+    Union(RawCsg),
+    #[serde(rename = "intersection")]
+    Intersection(RawCsg),
+    #[serde(rename = "difference")]
+    Difference(RawCsg),
+    #[serde(rename = "sdf")]
+    Sdf(RawSdf),
+}
+
+impl ObjectDef {
+    /// Builds the object, along with the lights it brings into the scene.
+    fn into_object(self) -> Result<(Geometry, Vec<LightSource>), Box<dyn Error>> {
+        let geometry = match self {
+            ObjectDef::Sphere(raw) => raw.into_sphere()?,
+            ObjectDef::Quad(raw) => raw.into_quad()?,
+            ObjectDef::Cube(raw) => raw.into_cube()?,
+            ObjectDef::Triangle(raw) => raw.into_triangle()?,
+            ObjectDef::Wavefront(raw) => raw.into_wavefront(&config_dir()?)?,
+            ObjectDef::Gltf(raw) => return raw.into_gltf(),
+            ObjectDef::Ply(raw) => raw.into_ply(&config_dir()?)?,
+            ObjectDef::Stl(raw) => raw.into_stl(&config_dir()?)?,
+            ObjectDef::Disk(raw) => raw.into_disk()?,
+            ObjectDef::Cylinder(raw) => raw.into_cylinder()?,
+            ObjectDef::Cone(raw) => raw.into_cone()?,
+            ObjectDef::Torus(raw) => raw.into_torus()?,
+            ObjectDef::Plane(raw) => raw.into_plane()?,
+            ObjectDef::Union(raw) => raw.into_csg(Operation::Union)?,
+            ObjectDef::Intersection(raw) => raw.into_csg(Operation::Intersection)?,
+            ObjectDef::Difference(raw) => raw.into_csg(Operation::Difference)?,
+            ObjectDef::Sdf(raw) => raw.into_sdf()?,
+        };
+        Ok((geometry, Vec::new()))
+    }
 }
 
 impl<'de> Deserialize<'de> for Geometry {
@@ -472,26 +2627,218 @@ impl<'de> Deserialize<'de> for Geometry {
     where
         D: serde::Deserializer<'de>,
     {
-        match ObjectDef::deserialize(deserializer)? {
-            ObjectDef::Sphere(raw) => Ok(raw.into_sphere().map_err(serde::de::Error::custom)?),
-            ObjectDef::Quad(raw) => Ok(raw.into_quad().map_err(serde::de::Error::custom)?),
-            ObjectDef::Cube(raw) => Ok(raw.into_cube().map_err(serde::de::Error::custom)?),
-            ObjectDef::Triangle(raw) => {
-                Ok(raw.into_triangle().map_err(serde::de::Error::custom)?)
+        let (geometry, lights) = ObjectDef::deserialize(deserializer)?
+            .into_object()
+            .map_err(serde::de::Error::custom)?;
+        if !lights.is_empty() {
+            return Err(serde::de::Error::custom(
+                "Lights can only be imported by objects at the top level of the scene",
+            ));
+        }
+        Ok(geometry)
+    }
+}
+
+/// An object at the top level of the scene, with the lights it brings along.
+struct RawObject {
+    geometry: Geometry,
+    lights: Vec<LightSource>,
+}
+
+impl<'de> Deserialize<'de> for RawObject {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (geometry, lights) = ObjectDef::deserialize(deserializer)?
+            .into_object()
+            .map_err(serde::de::Error::custom)?;
+        Ok(RawObject { geometry, lights })
+    }
+}
+
+/// The intensity of a point or spot light, per unit of solid angle, given directly or as the
+/// total power it would shine in every direction.
+fn point_intensity(
+    intensity: Option<f64>,
+    watts: Option<f64>,
+    lumens: Option<f64>,
+) -> Result<f64, Box<dyn Error>> {
+    match (intensity, watts, lumens) {
+        (Some(intensity), None, None) => Ok(intensity),
+        (None, Some(watts), None) => Ok(watts / (4.0 * PI)),
+        (None, None, Some(lumens)) => Ok(lumens / LUMINOUS_EFFICACY / (4.0 * PI)),
+        _ => Err("A light needs exactly one of `intensity`, `watts` or `lumens`".into()),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+enum LightDef {
+    #[serde(rename = "point")]
+    Point {
+        position: [f64; 3],
+        color: Option<[f64; 3]>,
+        kelvin: Option<f64>,
+        intensity: Option<f64>,
+        watts: Option<f64>,
+        lumens: Option<f64>,
+    },
+
+    #[serde(rename = "spot")]
+    Spot {
+        position: [f64; 3],
+        direction: [f64; 3],
+        angle: Option<f64>,
+        blend: Option<f64>,
+        color: Option<[f64; 3]>,
+        kelvin: Option<f64>,
+        intensity: Option<f64>,
+        watts: Option<f64>,
+        lumens: Option<f64>,
+    },
+
+    #[serde(rename = "directional")]
+    Directional {
+        direction: [f64; 3],
+        color: Option<[f64; 3]>,
+        kelvin: Option<f64>,
+        irradiance: Option<f64>,
+        lux: Option<f64>,
+    },
+}
+
+impl LightDef {
+    fn into_light_source(self) -> Result<LightSource, Box<dyn Error>> {
+        match self {
+            LightDef::Point {
+                position,
+                color,
+                kelvin,
+                intensity,
+                watts,
+                lumens,
+            } => {
+                let color =
+                    Vector3::from(color.unwrap_or([1.0; 3])).component_mul(&kelvin_color(kelvin)?);
+                let intensity = point_intensity(intensity, watts, lumens)?;
+                Ok(Point::light(Vector3::from(position), color * intensity))
+            }
+            LightDef::Spot {
+                position,
+                direction,
+                angle,
+                blend,
+                color,
+                kelvin,
+                intensity,
+                watts,
+                lumens,
+            } => {
+                let angle = angle.unwrap_or(45.0);
+                if !(0.0..=180.0).contains(&angle) {
+                    return Err(
+                        "The angle of a spot light must be between 0 and 180 degrees".into(),
+                    );
+                }
+                let blend = blend.unwrap_or(0.15);
+                if !(0.0..=1.0).contains(&blend) {
+                    return Err("The blend of a spot light must be between 0 and 1".into());
+                }
+                let direction = Vector3::from(direction);
+                if near_zero(&direction) {
+                    return Err("The direction of a spot light cannot be zero".into());
+                }
+                let color =
+                    Vector3::from(color.unwrap_or([1.0; 3])).component_mul(&kelvin_color(kelvin)?);
+                let intensity = point_intensity(intensity, watts, lumens)?;
+                let outer = angle / 2.0;
+                Ok(Spot::light(
+                    Vector3::from(position),
+                    direction,
+                    color * intensity,
+                    outer * (1.0 - blend),
+                    outer,
+                ))
             }
-            ObjectDef::Wavefront(raw) => {
-                Ok(raw.into_wavefront().map_err(serde::de::Error::custom)?)
+            LightDef::Directional {
+                direction,
+                color,
+                kelvin,
+                irradiance,
+                lux,
+            } => {
+                let direction = Vector3::from(direction);
+                if near_zero(&direction) {
+                    return Err("The direction of a directional light cannot be zero".into());
+                }
+                let color =
+                    Vector3::from(color.unwrap_or([1.0; 3])).component_mul(&kelvin_color(kelvin)?);
+                let irradiance = match (irradiance, lux) {
+                    (Some(irradiance), None) => irradiance,
+                    (None, Some(lux)) => lux / LUMINOUS_EFFICACY,
+                    _ => {
+                        return Err(
+                            "A directional light needs exactly one of `irradiance` or `lux`".into(),
+                        );
+                    }
+                };
+                Ok(Directional::light(direction, color * irradiance))
             }
         }
     }
 }
 
+impl<'de> Deserialize<'de> for LightSource {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        LightDef::deserialize(deserializer)?
+            .into_light_source()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
+struct RawConfig {
+    camera: CameraOptions,
+    atmosphere: Option<AtmosphereOptions>,
+    #[serde(default)]
+    objects: Vec<RawObject>,
+    #[serde(default)]
+    lights: Vec<LightSource>,
+}
+
+#[derive(Deserialize)]
+#[serde(from = "RawConfig")]
 pub struct Config {
     pub camera: CameraOptions,
-    #[serde(default)]
+    pub atmosphere: Option<AtmosphereOptions>,
     pub objects: Vec<Geometry>,
+    pub lights: Vec<LightSource>,
+}
+
+impl From<RawConfig> for Config {
+    /// Gathers the lights brought in by objects, like those of glTF files, with the others.
+    fn from(raw: RawConfig) -> Self {
+        let mut lights = raw.lights;
+        let objects = raw
+            .objects
+            .into_iter()
+            .map(|object| {
+                lights.extend(object.lights);
+                object.geometry
+            })
+            .collect();
+        Config {
+            camera: raw.camera,
+            atmosphere: raw.atmosphere,
+            objects,
+            lights,
+        }
+    }
 }
 
 impl fmt::Display for Config {
@@ -534,6 +2881,7 @@ impl fmt::Display for Config {
             ("Defocus Angle", format!("{}", self.camera.defocus_angle)),
             ("Focus Distance", format!("{}", self.camera.focus_dist)),
             ("Objects", format!("{}", self.objects.len())),
+            ("Lights", format!("{}", self.lights.len())),
         ]
         .map(|(k, v)| format!("│{:>14}: {:64}│", k.cyan().bold(), v))
         .join("\n");
@@ -562,3 +2910,469 @@ pub fn span_dump(config_content: &str, span: Range<usize>) -> String {
         .collect::<Vec<String>>()
         .join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::HitRecord;
+    use crate::geometry::Hittable;
+    use crate::interval::Interval;
+    use crate::material::texture::Sample;
+    use crate::ray::Ray;
+
+    /// A triangle scaled up by a child node of a node moved back along -z, with a spot light
+    /// above it and a camera looking along -x. Its material reads a 1x1 texture with the second
+    /// set of texture coordinates. The buffer holds the corners of the triangle, both sets of
+    /// texture coordinates and the PNG of the texture.
+    const SCENE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {
+            "KHR_lights_punctual": {
+                "lights": [{
+                    "type": "spot",
+                    "color": [1.0, 0.5, 0.25],
+                    "intensity": 683.0,
+                    "spot": { "innerConeAngle": 0.2, "outerConeAngle": 0.4 }
+                }]
+            }
+        },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "translation": [0.0, 0.0, -5.0], "children": [1, 2, 3] },
+            { "mesh": 0, "scale": [2.0, 2.0, 2.0] },
+            { "translation": [0.0, 2.0, 0.0], "extensions": { "KHR_lights_punctual": { "light": 0 } } },
+            { "camera": 0, "translation": [0.0, 1.0, 5.0], "rotation": [0.0, 0.70710678, 0.0, 0.70710678] }
+        ],
+        "cameras": [{ "type": "perspective", "perspective": { "yfov": 0.5, "znear": 0.1 } }],
+        "meshes": [{
+            "primitives": [{
+                "attributes": { "POSITION": 0, "TEXCOORD_0": 1, "TEXCOORD_1": 2 },
+                "material": 0
+            }]
+        }],
+        "materials": [{
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [0.8, 0.4, 0.2, 1.0],
+                "baseColorTexture": { "index": 0, "texCoord": 1 },
+                "metallicFactor": 0.5,
+                "roughnessFactor": 0.5,
+                "metallicRoughnessTexture": { "index": 0, "texCoord": 1 }
+            }
+        }],
+        "textures": [{ "source": 0 }],
+        "images": [{ "bufferView": 1, "mimeType": "image/png" }],
+        "accessors": [
+            {
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": 3,
+                "type": "VEC3",
+                "min": [-0.5, -0.5, 0.0],
+                "max": [0.5, 0.5, 0.0]
+            },
+            { "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC2" },
+            { "bufferView": 3, "componentType": 5126, "count": 3, "type": "VEC2" }
+        ],
+        "bufferViews": [
+            { "buffer": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 84, "byteLength": 69 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 24 },
+            { "buffer": 0, "byteOffset": 60, "byteLength": 24 }
+        ],
+        "buffers": [{
+            "byteLength": 153,
+            "uri": "data:application/octet-stream;base64,AAAAvwAAAL8AAAAAAAAAPwAAAL8AAAAAAAAAAAAAAD8AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABAPwAAgD4AAEA/AACAPgAAQD8AAIA+iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAIAAACQd1PeAAAADElEQVR4nGNgMP4PAAFpATPE7yvwAAAAAElFTkSuQmCC"
+        }]
+    }"#;
+
+    #[test]
+    fn test_gltf_material_factors() {
+        let (document, _, images) = gltf::import_slice(SCENE).unwrap();
+        let material = document.materials().next().unwrap();
+        let Material::Principled(principled) = gltf_material(&material, &images).unwrap() else {
+            panic!("glTF materials should become principled materials");
+        };
+
+        // The texel is (0.0, 0.2, 1.0), and colors are squared into linear light.
+        let sample = |texture: &Texture| texture.sample(0.5, 0.5, Vector3::default());
+        let base_color = sample(&principled.base_color);
+        assert!((base_color - Vector3::new(0.0, 0.4 * 0.04, 0.2)).norm() < 1e-6);
+        // Metallic is read from the blue channel and roughness from the green one as they are.
+        assert!((sample(&principled.metallic).x - 0.5).abs() < 1e-6);
+        assert!((sample(&principled.roughness).x - 0.1).abs() < 1e-6);
+        assert_eq!(sample(&principled.emission), Vector3::default());
+    }
+
+    #[test]
+    fn test_gltf_textures_share_a_set() {
+        let scene = SCENE.replace(
+            r#""metallicRoughnessTexture": { "index": 0, "texCoord": 1 }"#,
+            r#""metallicRoughnessTexture": { "index": 0 }"#,
+        );
+        let (document, _, images) = gltf::import_slice(scene).unwrap();
+        let material = document.materials().next().unwrap();
+        assert!(gltf_material(&material, &images).is_err());
+    }
+
+    #[test]
+    fn test_gltf_node_transforms() {
+        let (document, buffers, _) = gltf::import_slice(SCENE).unwrap();
+        let scene = document.default_scene().unwrap();
+        let default = Lambertian::material(SolidColor::texture(Vector3::from_element(1.0)));
+        let gltf = Gltf::geometry(&scene, &buffers, &[], &default, None);
+
+        // Only the scaled triangle reaches this far from the axis.
+        let record = hit_down(&gltf, 0.9, -0.9).expect("The scaled triangle should be hit");
+        assert!((record.t - 10.0).abs() < 1e-9);
+        // Its material reads the second set of texture coordinates.
+        assert!((record.u - 0.75).abs() < 1e-6 && (record.v - 0.25).abs() < 1e-6);
+
+        let transform = Matrix4::new_translation(&Vector3::x());
+        let lights = gltf_lights(&scene, &transform);
+        let [LightSource::Spot(spot)] = lights.as_slice() else {
+            panic!("The glTF spot light should become a spot light");
+        };
+        assert!((spot.position - Vector3::new(1.0, 2.0, -5.0)).norm() < 1e-6);
+        assert!((spot.direction + Vector3::z()).norm() < 1e-6);
+        assert!((spot.intensity - Vector3::new(1.0, 0.5, 0.25)).norm() < 1e-6);
+        assert!((spot.cos_outer - 0.4f64.cos()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gltf_camera() {
+        let (document, _, _) = gltf::import_slice(SCENE).unwrap();
+        let scene = document.default_scene().unwrap();
+
+        // Turned a quarter around y, the camera looks down -x, and is moved along by `transform`.
+        let transform = Matrix4::new_translation(&Vector3::x());
+        let camera = gltf_camera(&scene, None, &transform).unwrap();
+        assert!((camera.fov - 0.5f64.to_degrees()).abs() < 1e-6);
+        assert!((Vector3::from(camera.look_from) - Vector3::new(1.0, 1.0, 0.0)).norm() < 1e-6);
+        assert!((Vector3::from(camera.look_at) - Vector3::new(0.0, 1.0, 0.0)).norm() < 1e-6);
+        assert!((Vector3::from(camera.vup) - Vector3::y()).norm() < 1e-6);
+
+        assert!(gltf_camera(&scene, Some("Missing"), &transform).is_err());
+    }
+
+    #[test]
+    fn test_zero_light_direction() {
+        let spot =
+            "type = \"spot\"\nposition = [0.0, 1.0, 0.0]\ndirection = [0.0, 0.0, 0.0]\nwatts = 1.0";
+        assert!(toml::from_str::<LightSource>(spot).is_err());
+        let directional = "type = \"directional\"\ndirection = [0.0, 0.0, 0.0]\nlux = 1.0";
+        assert!(toml::from_str::<LightSource>(directional).is_err());
+        let directional = "type = \"directional\"\ndirection = [0.0, -1.0, 0.0]\nlux = 1.0";
+        assert!(toml::from_str::<LightSource>(directional).is_ok());
+    }
+
+    #[test]
+    fn test_degenerate_shapes() {
+        let material = "material = \"lambertian\"\nalbedo = [0.5, 0.5, 0.5]";
+        let disk = |fields: &str| {
+            toml::from_str::<RawDisk>(&format!("{fields}\n{material}"))
+                .unwrap()
+                .into_disk()
+        };
+        assert!(disk("position = [0.0, 0.0, 0.0]\nnormal = [0.0, 1.0, 0.0]\nradius = 1.0").is_ok());
+        assert!(
+            disk("position = [0.0, 0.0, 0.0]\nnormal = [0.0, 0.0, 0.0]\nradius = 1.0").is_err()
+        );
+        assert!(
+            disk("position = [0.0, 0.0, 0.0]\nnormal = [0.0, 1.0, 0.0]\nradius = 0.0").is_err()
+        );
+
+        let cylinder = |fields: &str| {
+            toml::from_str::<RawCylinder>(&format!("{fields}\n{material}"))
+                .unwrap()
+                .into_cylinder()
+        };
+        assert!(cylinder("a = [0.0, 0.0, 0.0]\nb = [0.0, 1.0, 0.0]\nradius = 1.0").is_ok());
+        assert!(cylinder("a = [0.0, 1.0, 0.0]\nb = [0.0, 1.0, 0.0]\nradius = 1.0").is_err());
+        assert!(cylinder("a = [0.0, 0.0, 0.0]\nb = [0.0, 1.0, 0.0]\nradius = -1.0").is_err());
+
+        let cone = |fields: &str| {
+            toml::from_str::<RawCone>(&format!("{fields}\n{material}"))
+                .unwrap()
+                .into_cone()
+        };
+        assert!(cone("a = [0.0, 0.0, 0.0]\nb = [0.0, 1.0, 0.0]\nradius = 1.0").is_ok());
+        assert!(cone("a = [0.0, 1.0, 0.0]\nb = [0.0, 1.0, 0.0]\nradius = 1.0").is_err());
+        assert!(cone("a = [0.0, 0.0, 0.0]\nb = [0.0, 1.0, 0.0]\nradius = 0.0").is_err());
+
+        let torus = |fields: &str| {
+            toml::from_str::<RawTorus>(&format!("position = [0.0, 0.0, 0.0]\n{fields}\n{material}"))
+                .unwrap()
+                .into_torus()
+        };
+        assert!(torus("major_radius = 1.0\nminor_radius = 0.25").is_ok());
+        assert!(torus("axis = [0.0, 0.0, 0.0]\nmajor_radius = 1.0\nminor_radius = 0.25").is_err());
+        assert!(torus("major_radius = 0.0\nminor_radius = 0.25").is_err());
+        assert!(torus("major_radius = 1.0\nminor_radius = -0.25").is_err());
+
+        let plane = |fields: &str| {
+            toml::from_str::<RawPlane>(&format!("position = [0.0, 0.0, 0.0]\n{fields}\n{material}"))
+                .unwrap()
+                .into_plane()
+        };
+        assert!(plane("normal = [0.0, 1.0, 0.0]").is_ok());
+        assert!(plane("normal = [0.0, 0.0, 0.0]").is_err());
+    }
+
+    #[test]
+    fn test_subsurface_and_emission_textures() {
+        let subsurface = "material = \"subsurface\"\nscattering_radius = [1.0, 0.5, 0.25]\n\
+                          color = { texture = \"checkered\", scale = 0.5 }";
+        assert!(matches!(
+            toml::from_str::<MaterialDef>(subsurface)
+                .unwrap()
+                .into_material()
+                .unwrap(),
+            Material::Subsurface(_)
+        ));
+
+        let volume = "density = 1.0\nalbedo = [0.5, 0.5, 0.5]\n\
+                      emission = { texture = \"noise\", seed = 1, high = [4.0, 2.0, 1.0] }";
+        let volume = toml::from_str::<RawVolume>(volume).unwrap();
+        let lobes = (volume.g, volume.back_g, volume.back_weight);
+        let Material::Isotropic(medium) =
+            phase_function(volume.albedo, volume.emission, lobes).unwrap()
+        else {
+            panic!("Expected an isotropic medium");
+        };
+        let glow = [Vector3::new(0.3, 0.2, 0.1), Vector3::new(-1.7, 0.4, 2.9)]
+            .map(|p| medium.emission.sample(0.0, 0.0, p));
+        assert_ne!(glow[0], glow[1]);
+    }
+
+    #[test]
+    fn test_marble_is_noise() {
+        let texture = |def: &str| {
+            toml::from_str::<TextureDef>(def)
+                .unwrap()
+                .into_texture()
+                .unwrap()
+        };
+        let marble = texture("texture = \"marble\"\nseed = 3");
+        let noise = texture("texture = \"noise\"\nseed = 3\nmode = \"marble\"");
+        for p in [Vector3::new(0.1, 0.2, 0.3), Vector3::new(-1.5, 2.5, 0.7)] {
+            assert_eq!(marble.sample(0.0, 0.0, p), noise.sample(0.0, 0.0, p));
+        }
+    }
+
+    #[test]
+    fn test_density_noise_options() {
+        let field = "type = \"noise\"\nmode = \"raw\"\noctaves = 3\ngain = 0.4\nseed = 1";
+        let Density::Noise(noise) = toml::from_str::<RawDensity>(field)
+            .unwrap()
+            .into_density()
+            .unwrap()
+        else {
+            panic!("A noise field should give a noise density");
+        };
+        assert!(matches!(noise.mode, NoiseMode::Raw));
+        assert_eq!((noise.octaves, noise.gain), (3, 0.4));
+    }
+
+    /// A fresh directory holding `files`, for loaders reading files next to the config.
+    fn fixture_dir(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("raytrace-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+        dir
+    }
+
+    fn hit_down(geometry: &Geometry, x: f64, y: f64) -> Option<HitRecord> {
+        let ray = Ray::new(Vector3::new(x, y, 5.0), -Vector3::z(), 0.0);
+        let mut record = HitRecord::default();
+        let interval = Interval::new(0.001, f64::INFINITY);
+        geometry
+            .hit(&ray, &interval, &mut record, &mut rand::rng())
+            .then_some(record)
+    }
+
+    #[test]
+    fn test_mtl_material_mapping() {
+        let dir = fixture_dir("mtl", &[]);
+        image::RgbImage::from_pixel(1, 1, image::Rgb([255, 0, 0]))
+            .save(dir.join("red.png"))
+            .unwrap();
+        let library = parse_mtl(
+            "newmtl matte\nKd 0.5 0.25 0.125\n\
+             newmtl shiny\nKd 0.1 0.1 0.1\nKs 0.9 0.8 0.7\nNs 998\n\
+             newmtl painted\nKd 0.1 0.1 0.1\nKs 0.9 0.9 0.9\nmap_Kd red.png\n\
+             newmtl glass\nKd 0.8 0.8 0.8\nd 0.5\nNi 1.33\n\
+             newmtl lamp\nKd 0.8 0.8 0.8\nKe 2 2 2\n"
+                .as_bytes(),
+        )
+        .unwrap()
+        .materials;
+        let material = |name: &str| mtl_material(&library[name], &dir).unwrap();
+        let sample = |texture: &Texture| texture.sample(0.5, 0.5, Vector3::default());
+
+        let Material::Lambertian(matte) = material("matte") else {
+            panic!("A diffuse mtl material should be lambertian");
+        };
+        assert_eq!(sample(&matte.texture), Vector3::new(0.5, 0.25, 0.125));
+
+        // Shinier than it is diffuse makes a metal, as sharp as its Phong exponent.
+        let Material::Metal(shiny) = material("shiny") else {
+            panic!("A specular mtl material should be metal");
+        };
+        assert!((shiny.distribution.alpha - 0.002f64.sqrt()).abs() < 1e-9);
+
+        // Unless a diffuse map is given.
+        let Material::Lambertian(painted) = material("painted") else {
+            panic!("A mapped mtl material should be lambertian");
+        };
+        assert!(matches!(painted.texture, Texture::Image(_)));
+        assert_eq!(sample(&painted.texture).y, 0.0);
+
+        let Material::Dielectric(glass) = material("glass") else {
+            panic!("A dissolved mtl material should be a dielectric");
+        };
+        assert!((glass.medium.refraction_index - 1.33).abs() < 1e-6);
+
+        assert!(matches!(material("lamp"), Material::Light(_)));
+    }
+
+    /// Two triangles side by side in groups `left` and `right`, where the right one uses a
+    /// material whose texture is missing.
+    const GROUPS_OBJ: &str = "mtllib groups.mtl\n\
+        v -2 0 0\nv -1 0 0\nv -1.5 1 0\nv 1 0 0\nv 2 0 0\nv 1.5 1 0\n\
+        g left\nusemtl red\nf 1 2 3\n\
+        g right\nusemtl broken\nf 4 5 6\n";
+    const GROUPS_MTL: &str = "newmtl red\nKd 1 0 0\nnewmtl broken\nmap_Kd missing.png\n";
+
+    #[test]
+    fn test_wavefront_groups_load_only_their_materials() {
+        let dir = fixture_dir(
+            "groups",
+            &[
+                ("groups.obj", GROUPS_OBJ.as_bytes()),
+                ("groups.mtl", GROUPS_MTL.as_bytes()),
+            ],
+        );
+        let load = |config: &str| {
+            toml::from_str::<RawWavefront>(config)
+                .unwrap()
+                .into_wavefront(&dir)
+        };
+
+        assert!(load("file = \"groups.obj\"").is_err());
+
+        let left = load("file = \"groups.obj\"\ngroup = \"left\"").unwrap();
+        let record = hit_down(&left, -1.5, 0.3).expect("The left triangle should be loaded");
+        assert!(matches!(record.material, Material::Lambertian(_)));
+        assert!(hit_down(&left, 1.5, 0.3).is_none());
+
+        // Overriding the broken material loads the whole file.
+        let overridden = load(
+            "file = \"groups.obj\"\n\
+             [materials.broken]\nmaterial = \"metal\"\nalbedo = [0.9, 0.9, 0.9]\nroughness = 0.1",
+        )
+        .unwrap();
+        let record = hit_down(&overridden, 1.5, 0.3).expect("The right triangle should be loaded");
+        assert!(matches!(record.material, Material::Metal(_)));
+        let record = hit_down(&overridden, -1.5, 0.3).expect("The left triangle should be loaded");
+        assert!(matches!(record.material, Material::Lambertian(_)));
+
+        assert!(load("file = \"groups.obj\"\n[materials.blue]\nmaterial = \"lambertian\"\nalbedo = [0, 0, 1]").is_err());
+    }
+
+    /// The color of the lambertian material `geometry` is shaded with at `x`, `y`.
+    fn color_down(geometry: &Geometry, x: f64, y: f64) -> Vector3<f64> {
+        let record = hit_down(geometry, x, y).expect("The mesh should be hit");
+        let Material::Lambertian(lambertian) = record.material else {
+            panic!("Vertex colors should give a lambertian material");
+        };
+        lambertian.texture.sample(record.u, record.v, record.point)
+    }
+
+    #[test]
+    fn test_ascii_ply_vertex_colors() {
+        let ply = "ply\nformat ascii 1.0\n\
+            element vertex 3\n\
+            property float x\nproperty float y\nproperty float z\n\
+            property uchar red\nproperty uchar green\nproperty uchar blue\n\
+            element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+            -1 -1 0 255 51 0\n1 -1 0 255 51 0\n0 1 0 255 51 0\n3 0 1 2\n";
+        let dir = fixture_dir("ascii-ply", &[("mesh.ply", ply.as_bytes())]);
+        let load = |config: &str| toml::from_str::<RawPly>(config).unwrap().into_ply(&dir);
+
+        // Vertex colors are squared into linear light like the colors of images.
+        let mesh = load("file = \"mesh.ply\"").unwrap();
+        let color = color_down(&mesh, 0.0, 0.0);
+        assert!((color - Vector3::new(1.0, 0.04, 0.0)).norm() < 1e-9);
+        assert!(hit_down(&mesh, 0.9, 0.9).is_none());
+
+        // A material replaces the colors.
+        let mesh = load(
+            "file = \"mesh.ply\"\nmaterial = \"metal\"\nalbedo = [0.9, 0.9, 0.9]\nroughness = 0.0",
+        )
+        .unwrap();
+        let record = hit_down(&mesh, 0.0, 0.0).expect("The mesh should be hit");
+        assert!(matches!(record.material, Material::Metal(_)));
+    }
+
+    #[test]
+    fn test_binary_ply() {
+        let mut ply = b"ply\nformat binary_little_endian 1.0\n\
+            element vertex 4\n\
+            property float x\nproperty float y\nproperty float z\n\
+            property float red\nproperty float green\nproperty float blue\n\
+            element face 1\nproperty list uchar int vertex_indices\nend_header\n"
+            .to_vec();
+        for [x, y] in [[-1.0f32, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]] {
+            for value in [x, y, 0.0, 0.5, 0.5, 0.5] {
+                ply.extend(value.to_le_bytes());
+            }
+        }
+        // A quad is split into two triangles.
+        ply.push(4);
+        for index in 0..4i32 {
+            ply.extend(index.to_le_bytes());
+        }
+        let dir = fixture_dir("binary-ply", &[("mesh.ply", &ply)]);
+        let mesh = toml::from_str::<RawPly>("file = \"mesh.ply\"")
+            .unwrap()
+            .into_ply(&dir)
+            .unwrap();
+
+        for (x, y) in [(0.5, -0.5), (-0.5, 0.5)] {
+            assert!((color_down(&mesh, x, y) - Vector3::from_element(0.25)).norm() < 1e-9);
+        }
+        assert!(hit_down(&mesh, 1.5, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_binary_stl() {
+        let mut stl = vec![0u8; 80];
+        stl.extend(1u32.to_le_bytes());
+        let facet = [
+            [0.0f32, 0.0, 1.0],
+            [-1.0, -1.0, 0.0],
+            [1.0, -1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        for value in facet.as_flattened() {
+            stl.extend(value.to_le_bytes());
+        }
+        stl.extend(0u16.to_le_bytes());
+        let dir = fixture_dir("binary-stl", &[("part.stl", &stl)]);
+        let mesh = toml::from_str::<RawStl>(
+            "file = \"part.stl\"\nmaterial = \"lambertian\"\nalbedo = [0.5, 0.5, 0.5]",
+        )
+        .unwrap()
+        .into_stl(&dir)
+        .unwrap();
+
+        let record = hit_down(&mesh, 0.0, 0.0).expect("The triangle should be hit");
+        assert!((record.t - 5.0).abs() < 1e-9);
+        assert!((record.normal - Vector3::z()).norm() < 1e-9);
+        assert!(hit_down(&mesh, 0.9, 0.9).is_none());
+    }
+}
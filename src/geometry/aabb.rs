@@ -63,6 +63,11 @@ impl Aabb {
     }
 
     pub fn hit(&self, r: &Ray, interval: &Interval) -> bool {
+        self.clip(r, interval).is_some()
+    }
+
+    /// The part of `interval` during which the ray is inside the box, if any.
+    pub fn clip(&self, r: &Ray, interval: &Interval) -> Option<Interval> {
         let ray_origin = r.origin;
         let ray_direction = r.direction;
 
@@ -81,11 +86,11 @@ impl Aabb {
             t_max = t_max.min(t1);
 
             if t_max <= t_min {
-                return false;
+                return None;
             }
         }
 
-        true
+        Some(Interval::new(t_min, t_max))
     }
 
     pub fn pad_to_minimums(&mut self) {
@@ -210,6 +215,25 @@ mod tests {
         assert!(!aabb.hit(&ray, &interval));
     }
 
+    #[test]
+    fn test_aabb_clip() {
+        let aabb = Aabb::from_points(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(
+            Vector3::new(-1.0, 0.5, 0.5),
+            Vector3::new(2.0, 0.0, 0.0),
+            1.0,
+        );
+
+        let clipped = aabb.clip(&ray, &Interval::new(0.0, 10.0)).unwrap();
+        assert_eq!(clipped.min, 0.5);
+        assert_eq!(clipped.max, 1.0);
+
+        let clipped = aabb.clip(&ray, &Interval::new(0.0, 0.75)).unwrap();
+        assert_eq!(clipped.max, 0.75);
+
+        assert!(aabb.clip(&ray, &Interval::new(0.0, 0.25)).is_none());
+    }
+
     #[test]
     fn test_aabb_vertices() {
         let aabb = Aabb::from_points(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
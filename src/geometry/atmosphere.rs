@@ -0,0 +1,199 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::prelude::*;
+use rand::rngs::ThreadRng;
+
+/// A medium filling the whole scene around `world`, with `density` at `height` thinning out by a
+/// factor of `e` every `1 / falloff` units above it.
+#[derive(Debug, Clone)]
+pub struct Atmosphere {
+    world: Box<Geometry>,
+    density: f64,
+    falloff: f64,
+    height: f64,
+    phase_function: Material,
+}
+
+impl Atmosphere {
+    pub fn new(
+        world: Geometry,
+        density: f64,
+        falloff: f64,
+        height: f64,
+        phase_function: Material,
+    ) -> Self {
+        Atmosphere {
+            world: Box::new(world),
+            density,
+            falloff,
+            height,
+            phase_function,
+        }
+    }
+    pub fn geometry(
+        world: Geometry,
+        density: f64,
+        falloff: f64,
+        height: f64,
+        phase_function: Material,
+    ) -> Geometry {
+        Geometry::Atmosphere(Atmosphere::new(
+            world,
+            density,
+            falloff,
+            height,
+            phase_function,
+        ))
+    }
+
+    /// Samples how far light travels from `origin` along the unit `direction` before scattering,
+    /// or `None` if it rises out of the atmosphere first.
+    fn free_flight(
+        &self,
+        origin: &Vector3<f64>,
+        direction: &Vector3<f64>,
+        rng: &mut ThreadRng,
+    ) -> Option<f64> {
+        let optical_depth = -(1.0 - rng.random::<f64>()).ln();
+        self.distance_to_depth(origin, direction, optical_depth)
+    }
+
+    /// How far from `origin` along the unit `direction` the atmosphere reaches an
+    /// `optical_depth`, or `None` if it never gets that thick.
+    fn distance_to_depth(
+        &self,
+        origin: &Vector3<f64>,
+        direction: &Vector3<f64>,
+        optical_depth: f64,
+    ) -> Option<f64> {
+        let start_density = self.density * (-self.falloff * (origin.y - self.height)).exp();
+
+        // The optical depth of an exponential atmosphere has a closed form, which is inverted.
+        let rate = self.falloff * direction.y;
+        if rate.abs() < 1e-9 {
+            return Some(optical_depth / start_density);
+        }
+        let remaining = 1.0 - optical_depth * rate / start_density;
+        (remaining > 0.0).then(|| -remaining.ln() / rate)
+    }
+}
+
+impl Hittable for Atmosphere {
+    fn hit(
+        &self,
+        r: &Ray,
+        interval: &Interval,
+        record: &mut HitRecord,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        let hit_world = self.world.hit(r, interval, record, rng);
+        let t_max = match hit_world {
+            true => record.t,
+            false => interval.max,
+        };
+
+        let ray_length = r.direction.norm();
+        let Some(distance) =
+            self.free_flight(&r.at(interval.min), &(r.direction / ray_length), rng)
+        else {
+            return hit_world;
+        };
+
+        let t = interval.min + distance / ray_length;
+        if t >= t_max {
+            return hit_world;
+        }
+
+        record.t = t;
+        record.point = r.at(t);
+        record.normal = Vector3::new(1.0, 0.0, 0.0);
+        record.front_face = true;
+        record.material = self.phase_function.clone();
+        (record.u, record.v) = (0.0, 0.0);
+        record.tangent = Vector3::default();
+        record.bitangent = Vector3::default();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.world.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::empty::Empty;
+    use crate::material::isotropic::Isotropic;
+    use crate::material::texture::SolidColor;
+
+    /// The optical depth from `origin` to `distance` along `direction`, integrated numerically.
+    fn integrate_depth(
+        atmosphere: &Atmosphere,
+        origin: &Vector3<f64>,
+        direction: &Vector3<f64>,
+        distance: f64,
+    ) -> f64 {
+        let steps = 100_000;
+        let step = distance / steps as f64;
+        (0..steps)
+            .map(|i| {
+                let y = origin.y + direction.y * (i as f64 + 0.5) * step;
+                atmosphere.density * (-atmosphere.falloff * (y - atmosphere.height)).exp() * step
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_distance_to_depth_matches_integral() {
+        let atmosphere = Atmosphere::new(
+            Empty::geometry(),
+            0.3,
+            0.6,
+            1.0,
+            Isotropic::material(
+                SolidColor::texture(Vector3::from_element(0.5)),
+                SolidColor::texture(Vector3::default()),
+            ),
+        );
+        let origin = Vector3::new(0.0, 2.0, 0.0);
+        for direction in [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.5, 0.0).normalize(),
+            Vector3::new(1.0, -0.5, 0.0).normalize(),
+            Vector3::new(0.0, -1.0, 0.0),
+        ] {
+            for optical_depth in [0.01, 0.1, 0.5] {
+                let distance = atmosphere
+                    .distance_to_depth(&origin, &direction, optical_depth)
+                    .unwrap();
+                let integrated = integrate_depth(&atmosphere, &origin, &direction, distance);
+                assert!(
+                    (integrated - optical_depth).abs() < 1e-6,
+                    "{integrated} != {optical_depth}"
+                );
+            }
+        }
+
+        // Straight up, the whole atmosphere above holds an optical depth of density / falloff.
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let total = 0.3 * (-0.6f64).exp() / 0.6;
+        assert!(
+            atmosphere
+                .distance_to_depth(&origin, &up, total * 0.99)
+                .is_some()
+        );
+        assert!(
+            atmosphere
+                .distance_to_depth(&origin, &up, total * 1.01)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::interval::Interval;
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+
+/// Step used to difference textures varying with the hit point.
+const DELTA: f64 = 1e-4;
+
+const LUMINANCE: Vector3<f64> = Vector3::new(0.2126, 0.7152, 0.0722);
+
+/// Perturbs the normals of a geometry as if its surface were raised by the luminance of a
+/// texture, scaled by `strength`.
+#[derive(Debug, Clone)]
+pub struct Bump {
+    geometry: Box<Geometry>,
+    texture: Texture,
+    strength: f64,
+}
+
+impl Bump {
+    pub fn new(geometry: Geometry, texture: Texture, strength: f64) -> Self {
+        let geometry = Box::new(geometry);
+        Bump {
+            geometry,
+            texture,
+            strength,
+        }
+    }
+
+    pub fn geometry(geometry: Geometry, texture: Texture, strength: f64) -> Geometry {
+        Geometry::Bump(Bump::new(geometry, texture, strength))
+    }
+
+    fn height(&self, u: f64, v: f64, p: Vector3<f64>) -> f64 {
+        let color = match &self.texture {
+            // Images are blended between pixels, otherwise their gradient vanishes inside pixels.
+            Texture::Image(image) => image.bilinear(u, v),
+            texture => texture.sample(u, v, p),
+        };
+        color.dot(&LUMINANCE)
+    }
+
+    /// The gradient of the height along the surface with respect to the hit point and to its
+    /// texture coordinates.
+    fn gradient(&self, record: &HitRecord, normal: &Vector3<f64>) -> Vector3<f64> {
+        let (u, v, p) = (record.u, record.v, record.point);
+        let height = self.height(u, v, p);
+
+        let spatial = Vector3::from_fn(|axis, _| {
+            let mut offset = p;
+            offset[axis] += DELTA;
+            (self.height(u, v, offset) - height) / DELTA
+        });
+        let spatial = spatial - normal * normal.dot(&spatial);
+
+        let (du, dv) = match &self.texture {
+            Texture::Image(image) => (
+                1.0 / image.data.width() as f64,
+                1.0 / image.data.height() as f64,
+            ),
+            _ => (DELTA, DELTA),
+        };
+        let along_u = (self.height(u + du, v, p) - height) / du;
+        let along_v = (self.height(u, v + dv, p) - height) / dv;
+
+        spatial + record.tangent * along_u + record.bitangent * along_v
+    }
+}
+
+impl Hittable for Bump {
+    fn hit(
+        &self,
+        r: &Ray,
+        interval: &Interval,
+        record: &mut HitRecord,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        if !self.geometry.hit(r, interval, record, rng) {
+            return false;
+        }
+
+        let outward_normal = if record.front_face {
+            record.normal
+        } else {
+            -record.normal
+        };
+        let gradient = self.gradient(record, &outward_normal);
+        let bumped = (outward_normal - gradient * self.strength)
+            .try_normalize(f64::EPSILON)
+            .unwrap_or(outward_normal);
+
+        record.normal = if record.front_face { bumped } else { -bumped };
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.geometry.bounding_box()
+    }
+}
@@ -0,0 +1,182 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::disk;
+use crate::geometry::disk::disk_uv;
+use crate::geometry::frame::Frame;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::math::solve_quadratic;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
+
+/// A cone with a base of `radius` centered on `a` narrowing to its apex at `b`, optionally closed
+/// by a disk at its base.
+#[derive(Debug, Clone)]
+pub struct Cone {
+    frame: Frame,
+    height: f64,
+    radius: f64,
+    capped: bool,
+    material: Material,
+    bbox: Aabb,
+}
+
+impl Cone {
+    pub fn new(
+        a: Vector3<f64>,
+        b: Vector3<f64>,
+        radius: f64,
+        capped: bool,
+        material: Material,
+    ) -> Self {
+        let frame = Frame::new(a, b - a);
+        let height = (b - a).norm();
+
+        let extent = frame.disk_extent(radius);
+        let bbox = Aabb::from_boxes(
+            &Aabb::from_points(a - extent, a + extent),
+            &Aabb::from_points(b, b),
+        );
+
+        Cone {
+            frame,
+            height,
+            radius,
+            capped,
+            material,
+            bbox,
+        }
+    }
+
+    pub fn geometry(
+        a: Vector3<f64>,
+        b: Vector3<f64>,
+        radius: f64,
+        capped: bool,
+        material: Material,
+    ) -> Geometry {
+        Geometry::Cone(Cone::new(a, b, radius, capped, material))
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, interval: &Interval, record: &mut HitRecord, _: &mut ThreadRng) -> bool {
+        let local = self.frame.ray_to_local(r);
+        let (o, d) = (local.origin, local.direction);
+
+        // The side is where x^2 + y^2 = (k * (height - z))^2, narrowing by k per unit of height.
+        let k2 = (self.radius / self.height).powi(2);
+        let apex = self.height - o.z;
+        let side = solve_quadratic(
+            d.x * d.x + d.y * d.y - k2 * d.z * d.z,
+            2.0 * (o.x * d.x + o.y * d.y + k2 * apex * d.z),
+            o.x * o.x + o.y * o.y - k2 * apex * apex,
+        )
+        .into_iter()
+        .find(|t| interval.surrounds(*t) && (0.0..=self.height).contains(&local.at(*t).z));
+
+        let cap = match self.capped {
+            true => disk::intersect(&local, 0.0, self.radius).filter(|t| interval.surrounds(*t)),
+            false => None,
+        };
+
+        let (t, normal, (u, v), (tangent, bitangent)) = match (side, cap) {
+            (Some(side), cap) if cap.is_none_or(|cap| side <= cap) => {
+                let p = local.at(side);
+                let u = (p.y.atan2(p.x) / (2.0 * PI)).rem_euclid(1.0);
+                let normal = Vector3::new(p.x, p.y, k2 * (self.height - p.z))
+                    .try_normalize(f64::EPSILON)
+                    .unwrap_or(Vector3::z());
+                let tangent = Vector3::new(-p.y, p.x, 0.0)
+                    .try_normalize(f64::EPSILON)
+                    .unwrap_or_default();
+                let bitangent = Vector3::new(-p.x, -p.y, self.height - p.z)
+                    .try_normalize(f64::EPSILON)
+                    .unwrap_or_default();
+                (side, normal, (u, p.z / self.height), (tangent, bitangent))
+            }
+            (_, Some(cap)) => {
+                let (uv, tangents) = disk_uv(&local.at(cap), self.radius);
+                (cap, -Vector3::z(), uv, tangents)
+            }
+            _ => return false,
+        };
+
+        record.t = t;
+        record.point = r.at(t);
+        record.set_face_normal(r, &self.frame.vector_to_world(&normal));
+        record.material = self.material.clone();
+        (record.u, record.v) = (u, v);
+        record.tangent = self.frame.vector_to_world(&tangent);
+        record.bitangent = self.frame.vector_to_world(&bitangent);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+
+    fn material() -> Material {
+        Lambertian::material(SolidColor::texture(Vector3::from_element(0.5)))
+    }
+
+    fn hit(cone: &Cone, origin: [f64; 3], direction: [f64; 3]) -> Option<HitRecord> {
+        let ray = Ray::new(Vector3::from(origin), Vector3::from(direction), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut record = HitRecord::default();
+        cone.hit(&ray, &interval, &mut record, &mut rand::rng())
+            .then_some(record)
+    }
+
+    fn cone(capped: bool) -> Cone {
+        Cone::new(
+            Vector3::zeros(),
+            Vector3::new(0.0, 0.0, 2.0),
+            1.0,
+            capped,
+            material(),
+        )
+    }
+
+    #[test]
+    fn test_cone_side() {
+        let record = hit(&cone(true), [5.0, 0.0, 1.0], [-1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - 4.5).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.normal - Vector3::new(2.0, 0.0, 1.0).normalize()).norm() < 1e-9);
+        assert!((record.u - 0.0).abs() < 1e-9 && (record.v - 0.5).abs() < 1e-9);
+
+        let record = hit(&cone(true), [0.0, 5.0, 0.5], [0.0, -1.0, 0.0]).unwrap();
+        assert!((record.t - 4.25).abs() < 1e-9);
+        assert!((record.u - 0.25).abs() < 1e-9 && (record.v - 0.25).abs() < 1e-9);
+
+        assert!(hit(&cone(true), [5.0, 0.0, 2.5], [-1.0, 0.0, 0.0]).is_none());
+        assert!(hit(&cone(true), [5.0, 0.0, -0.5], [-1.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_cone_cap() {
+        let record = hit(&cone(true), [0.5, 0.0, -5.0], [0.0, 0.0, 1.0]).unwrap();
+        assert!((record.t - 5.0).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.normal - -Vector3::z()).norm() < 1e-9);
+        assert!((record.u - 0.0).abs() < 1e-9 && (record.v - 0.5).abs() < 1e-9);
+
+        // Without the cap, the ray enters the cone from below and meets the side from within.
+        let record = hit(&cone(false), [0.5, 0.0, -5.0], [0.0, 0.0, 1.0]).unwrap();
+        assert!((record.t - 6.0).abs() < 1e-9);
+        assert!(!record.front_face);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::interval::Interval;
+use crate::ray::Ray;
+use rand::rngs::ThreadRng;
+
+/// Gap left after each crossing before searching for the next one.
+const STEP: f64 = 0.0001;
+
+/// Crossings searched per child before giving up, in case a child keeps reporting the same hit.
+const MAX_CROSSINGS: usize = 64;
+
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl Operation {
+    fn inside(&self, a: bool, b: bool) -> bool {
+        match self {
+            Operation::Union => a || b,
+            Operation::Intersection => a && b,
+            Operation::Difference => a && !b,
+        }
+    }
+}
+
+/// Combines two closed geometries by tracking where a ray enters and leaves each of them, so the
+/// surface lies wherever the ray passes between the inside and the outside of the combination.
+#[derive(Debug, Clone)]
+pub struct Csg {
+    operation: Operation,
+    a: Box<Geometry>,
+    b: Box<Geometry>,
+    bbox: Aabb,
+}
+
+impl Csg {
+    pub fn new(operation: Operation, a: Geometry, b: Geometry) -> Self {
+        let (a_box, b_box) = (a.bounding_box(), b.bounding_box());
+        let bbox = match operation {
+            Operation::Union => Aabb::from_boxes(&a_box, &b_box),
+            Operation::Intersection => Aabb::new(
+                overlap(a_box.x, b_box.x),
+                overlap(a_box.y, b_box.y),
+                overlap(a_box.z, b_box.z),
+            ),
+            Operation::Difference => a_box,
+        };
+
+        Csg {
+            operation,
+            a: Box::new(a),
+            b: Box::new(b),
+            bbox,
+        }
+    }
+
+    pub fn geometry(operation: Operation, a: Geometry, b: Geometry) -> Geometry {
+        Geometry::Csg(Csg::new(operation, a, b))
+    }
+}
+
+fn overlap(a: Interval, b: Interval) -> Interval {
+    let min = a.min.max(b.min);
+    Interval::new(min, a.max.min(b.max).max(min))
+}
+
+/// Every point within `interval` where the ray crosses the surface of `geometry`, followed by the
+/// first one past it, which still tells whether the ray ends inside of `geometry`.
+fn crossings(
+    geometry: &Geometry,
+    r: &Ray,
+    interval: &Interval,
+    rng: &mut ThreadRng,
+) -> Vec<HitRecord> {
+    let mut crossings = Vec::new();
+    let mut record = HitRecord::default();
+    let mut min = interval.min;
+
+    while crossings.len() < MAX_CROSSINGS
+        && geometry.hit(r, &Interval::new(min, f64::INFINITY), &mut record, rng)
+    {
+        min = record.t + STEP;
+        crossings.push(record.clone());
+        if record.t > interval.max {
+            break;
+        }
+    }
+
+    crossings
+}
+
+impl Hittable for Csg {
+    fn hit(
+        &self,
+        r: &Ray,
+        interval: &Interval,
+        record: &mut HitRecord,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        if !self.bbox.hit(r, interval) {
+            return false;
+        }
+
+        let a = crossings(&self.a, r, interval, rng);
+        let b = crossings(&self.b, r, interval, rng);
+
+        // A ray whose first crossing leaves a geometry starts inside of it.
+        let mut inside_a = a.first().is_some_and(|crossing| !crossing.front_face);
+        let mut inside_b = b.first().is_some_and(|crossing| !crossing.front_face);
+        let mut inside = self.operation.inside(inside_a, inside_b);
+
+        let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+        loop {
+            let from_a = match (a.peek(), b.peek()) {
+                (Some(next_a), Some(next_b)) => next_a.t <= next_b.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return false,
+            };
+            let Some(crossing) = (if from_a { a.next() } else { b.next() }) else {
+                return false;
+            };
+            // Crossing from the front of a surface enters the geometry, otherwise it leaves.
+            match from_a {
+                true => inside_a = crossing.front_face,
+                false => inside_b = crossing.front_face,
+            }
+            if crossing.t > interval.max {
+                return false;
+            }
+
+            let now_inside = self.operation.inside(inside_a, inside_b);
+            if now_inside != inside {
+                // The normal already faces the ray, only which side of the combination it
+                // belongs to can change, such as the walls of a hole cut by a difference.
+                *record = crossing;
+                record.front_face = now_inside;
+                return true;
+            }
+            inside = now_inside;
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::cube::Cube;
+    use crate::geometry::sphere::Sphere;
+    use crate::material::Material;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+    use nalgebra::Vector3;
+
+    fn material() -> Material {
+        Lambertian::material(SolidColor::texture(Vector3::from_element(0.5)))
+    }
+
+    fn sphere(center: [f64; 3], radius: f64) -> Geometry {
+        Sphere::geometry(Vector3::from(center), Vector3::zeros(), radius, material())
+    }
+
+    fn cube(size: f64) -> Geometry {
+        Cube::geometry(
+            Vector3::from_element(-size),
+            Vector3::from_element(size),
+            material(),
+        )
+    }
+
+    fn hit(csg: &Csg, origin: [f64; 3], max: f64) -> Option<HitRecord> {
+        let ray = Ray::new(Vector3::from(origin), Vector3::x(), 0.0);
+        let interval = Interval::new(0.001, max);
+        let mut record = HitRecord::default();
+        csg.hit(&ray, &interval, &mut record, &mut rand::rng())
+            .then_some(record)
+    }
+
+    #[test]
+    fn test_union() {
+        let union = Csg::new(
+            Operation::Union,
+            sphere([-0.5, 0.0, 0.0], 1.0),
+            sphere([0.5, 0.0, 0.0], 1.0),
+        );
+
+        let record = hit(&union, [-5.0, 0.0, 0.0], f64::INFINITY).unwrap();
+        assert!((record.t - 3.5).abs() < 1e-9);
+        assert!(record.front_face);
+
+        // Starting inside both spheres, the surfaces within the union are skipped.
+        let record = hit(&union, [0.0, 0.0, 0.0], f64::INFINITY).unwrap();
+        assert!((record.t - 1.5).abs() < 1e-9);
+        assert!(!record.front_face);
+        assert!((record.normal - -Vector3::x()).norm() < 1e-9);
+
+        assert!(hit(&union, [-5.0, 0.0, 0.0], 3.0).is_none());
+        assert!(hit(&union, [-5.0, 2.0, 0.0], f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_intersection() {
+        let intersection = Csg::new(
+            Operation::Intersection,
+            cube(1.0),
+            sphere([0.0, 0.0, 0.0], 1.2),
+        );
+
+        // The ray enters the sphere first, but only reaches the intersection at the cube.
+        let record = hit(&intersection, [-5.0, 0.0, 0.0], f64::INFINITY).unwrap();
+        assert!((record.t - 4.0).abs() < 1e-9);
+        assert!(record.front_face);
+
+        // Near the corners of the cube, the ray misses the sphere.
+        assert!(hit(&intersection, [-5.0, 0.9, 0.9], f64::INFINITY).is_none());
+
+        let record = hit(&intersection, [0.0, 0.0, 0.0], f64::INFINITY).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-9);
+        assert!(!record.front_face);
+    }
+
+    #[test]
+    fn test_difference() {
+        let difference = Csg::new(
+            Operation::Difference,
+            cube(1.0),
+            sphere([0.0, 0.0, 0.0], 0.5),
+        );
+
+        let record = hit(&difference, [-5.0, 0.0, 0.0], f64::INFINITY).unwrap();
+        assert!((record.t - 4.0).abs() < 1e-9);
+        assert!(record.front_face);
+
+        // Entering the sphere leaves the difference through the wall of its hole, which faces
+        // the ray as the sphere's outside does.
+        let record = hit(&difference, [-0.75, 0.0, 0.0], f64::INFINITY).unwrap();
+        assert!((record.t - 0.25).abs() < 1e-9);
+        assert!(!record.front_face);
+        assert!((record.normal - -Vector3::x()).norm() < 1e-9);
+
+        // Leaving the sphere from within the hole enters the difference.
+        let record = hit(&difference, [0.0, 0.0, 0.0], f64::INFINITY).unwrap();
+        assert!((record.t - 0.5).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.normal - -Vector3::x()).norm() < 1e-9);
+
+        // Crossings past the end of the ray still tell it starts within the hole.
+        assert!(hit(&difference, [0.0, 0.0, 0.0], 0.4).is_none());
+        assert!(hit(&difference, [0.0, 0.0, 0.0], 0.6).is_some());
+    }
+}
@@ -24,13 +24,14 @@ impl Cube {
         let dy = Vector3::new(0.0, max.y - min.y, 0.0);
         let dz = Vector3::new(0.0, 0.0, max.z - min.z);
 
+        let side = |q, u, v| Quad::geometry(q, u, v, material.clone(), None);
         let primitives = [
-            Quad::geometry(Vector3::new(min.x, min.y, max.z), dx, dy, material.clone()),
-            Quad::geometry(Vector3::new(max.x, min.y, max.z), -dz, dy, material.clone()),
-            Quad::geometry(Vector3::new(max.x, min.y, min.z), -dx, dy, material.clone()),
-            Quad::geometry(Vector3::new(min.x, min.y, min.z), dz, dy, material.clone()),
-            Quad::geometry(Vector3::new(min.x, max.y, max.z), dx, -dz, material.clone()),
-            Quad::geometry(Vector3::new(min.x, min.y, min.z), dx, dz, material.clone()),
+            side(Vector3::new(min.x, min.y, max.z), dx, dy),
+            side(Vector3::new(max.x, min.y, max.z), -dz, dy),
+            side(Vector3::new(max.x, min.y, min.z), -dx, dy),
+            side(Vector3::new(min.x, min.y, min.z), dz, dy),
+            side(Vector3::new(min.x, max.y, max.z), dx, -dz),
+            side(Vector3::new(min.x, min.y, min.z), dx, dz),
         ];
 
         let children = Box::new(BvhNode::new(primitives.to_vec()));
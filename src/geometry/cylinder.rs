@@ -0,0 +1,200 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::disk;
+use crate::geometry::disk::disk_uv;
+use crate::geometry::frame::Frame;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::math::solve_quadratic;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
+
+/// A cylinder of `radius` around the segment from `a` to `b`, optionally closed by disks at both
+/// ends.
+#[derive(Debug, Clone)]
+pub struct Cylinder {
+    frame: Frame,
+    height: f64,
+    radius: f64,
+    capped: bool,
+    material: Material,
+    bbox: Aabb,
+}
+
+impl Cylinder {
+    pub fn new(
+        a: Vector3<f64>,
+        b: Vector3<f64>,
+        radius: f64,
+        capped: bool,
+        material: Material,
+    ) -> Self {
+        let frame = Frame::new(a, b - a);
+        let height = (b - a).norm();
+
+        let extent = frame.disk_extent(radius);
+        let bbox = Aabb::from_boxes(
+            &Aabb::from_points(a - extent, a + extent),
+            &Aabb::from_points(b - extent, b + extent),
+        );
+
+        Cylinder {
+            frame,
+            height,
+            radius,
+            capped,
+            material,
+            bbox,
+        }
+    }
+
+    pub fn geometry(
+        a: Vector3<f64>,
+        b: Vector3<f64>,
+        radius: f64,
+        capped: bool,
+        material: Material,
+    ) -> Geometry {
+        Geometry::Cylinder(Cylinder::new(a, b, radius, capped, material))
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, interval: &Interval, record: &mut HitRecord, _: &mut ThreadRng) -> bool {
+        let local = self.frame.ray_to_local(r);
+        let (o, d) = (local.origin, local.direction);
+
+        let side = solve_quadratic(
+            d.x * d.x + d.y * d.y,
+            2.0 * (o.x * d.x + o.y * d.y),
+            o.x * o.x + o.y * o.y - self.radius * self.radius,
+        )
+        .into_iter()
+        .find(|t| interval.surrounds(*t) && (0.0..=self.height).contains(&local.at(*t).z));
+
+        let caps = match self.capped {
+            true => [0.0, self.height]
+                .into_iter()
+                .filter_map(|height| disk::intersect(&local, height, self.radius))
+                .filter(|t| interval.surrounds(*t))
+                .min_by(f64::total_cmp),
+            false => None,
+        };
+
+        let (t, normal, (u, v), (tangent, bitangent)) = match (side, caps) {
+            (Some(side), caps) if caps.is_none_or(|cap| side <= cap) => {
+                let p = local.at(side);
+                let u = (p.y.atan2(p.x) / (2.0 * PI)).rem_euclid(1.0);
+                let tangent = Vector3::new(-p.y, p.x, 0.0) / self.radius;
+                let normal = Vector3::new(p.x, p.y, 0.0) / self.radius;
+                (
+                    side,
+                    normal,
+                    (u, p.z / self.height),
+                    (tangent, Vector3::z()),
+                )
+            }
+            (_, Some(cap)) => {
+                let p = local.at(cap);
+                let normal = match p.z < self.height / 2.0 {
+                    true => -Vector3::z(),
+                    false => Vector3::z(),
+                };
+                let (uv, tangents) = disk_uv(&p, self.radius);
+                (cap, normal, uv, tangents)
+            }
+            _ => return false,
+        };
+
+        record.t = t;
+        record.point = r.at(t);
+        record.set_face_normal(r, &self.frame.vector_to_world(&normal));
+        record.material = self.material.clone();
+        (record.u, record.v) = (u, v);
+        record.tangent = self.frame.vector_to_world(&tangent);
+        record.bitangent = self.frame.vector_to_world(&bitangent);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+
+    fn material() -> Material {
+        Lambertian::material(SolidColor::texture(Vector3::from_element(0.5)))
+    }
+
+    fn hit(cylinder: &Cylinder, origin: [f64; 3], direction: [f64; 3]) -> Option<HitRecord> {
+        let ray = Ray::new(Vector3::from(origin), Vector3::from(direction), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut record = HitRecord::default();
+        cylinder
+            .hit(&ray, &interval, &mut record, &mut rand::rng())
+            .then_some(record)
+    }
+
+    fn cylinder(capped: bool) -> Cylinder {
+        Cylinder::new(
+            Vector3::zeros(),
+            Vector3::new(0.0, 0.0, 2.0),
+            1.0,
+            capped,
+            material(),
+        )
+    }
+
+    #[test]
+    fn test_cylinder_side() {
+        for capped in [true, false] {
+            let record = hit(&cylinder(capped), [5.0, 0.0, 0.5], [-1.0, 0.0, 0.0]).unwrap();
+            assert!((record.t - 4.0).abs() < 1e-9);
+            assert!(record.front_face);
+            assert!((record.normal - Vector3::x()).norm() < 1e-9);
+            assert!((record.u - 0.0).abs() < 1e-9 && (record.v - 0.25).abs() < 1e-9);
+
+            let record = hit(&cylinder(capped), [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]).unwrap();
+            assert!((record.t - 1.0).abs() < 1e-9);
+            assert!(!record.front_face);
+
+            assert!(hit(&cylinder(capped), [5.0, 0.0, 2.5], [-1.0, 0.0, 0.0]).is_none());
+            assert!(hit(&cylinder(capped), [5.0, 2.0, 1.0], [-1.0, 0.0, 0.0]).is_none());
+        }
+    }
+
+    #[test]
+    fn test_cylinder_caps() {
+        let record = hit(&cylinder(true), [0.5, 0.0, 5.0], [0.0, 0.0, -1.0]).unwrap();
+        assert!((record.t - 3.0).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.normal - Vector3::z()).norm() < 1e-9);
+        assert!((record.u - 0.0).abs() < 1e-9 && (record.v - 0.5).abs() < 1e-9);
+
+        let record = hit(&cylinder(true), [0.0, -0.5, -5.0], [0.0, 0.0, 1.0]).unwrap();
+        assert!((record.t - 5.0).abs() < 1e-9);
+        assert!((record.normal - -Vector3::z()).norm() < 1e-9);
+        assert!((record.u - 0.75).abs() < 1e-9 && (record.v - 0.5).abs() < 1e-9);
+
+        // Without caps, a ray along the axis passes straight through.
+        assert!(hit(&cylinder(false), [0.5, 0.0, 5.0], [0.0, 0.0, -1.0]).is_none());
+
+        // A slanted ray through the open top reaches the inside of the wall.
+        let record = hit(&cylinder(false), [0.0, 0.0, 2.5], [1.0, 0.0, -1.0]).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-9);
+        assert!(!record.front_face);
+        assert!((record.normal - -Vector3::x()).norm() < 1e-9);
+        let record = hit(&cylinder(true), [0.0, 0.0, 2.5], [1.0, 0.0, -1.0]).unwrap();
+        assert!((record.t - 0.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::frame::Frame;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
+
+/// Intersects a ray in local space with the disk of `radius` lying across the z axis at
+/// `height`, returning the ray's `t` at the hit.
+pub fn intersect(local: &Ray, height: f64, radius: f64) -> Option<f64> {
+    if local.direction.z.abs() < 1e-8 {
+        return None; // Ray is parallel to the disk
+    }
+
+    let t = (height - local.origin.z) / local.direction.z;
+    let p = local.at(t);
+    match p.x * p.x + p.y * p.y <= radius * radius {
+        true => Some(t),
+        false => None,
+    }
+}
+
+/// The texture coordinates of the local point `p` on a disk of `radius`, winding `u` around the
+/// center and growing `v` outwards, along with the directions in which they increase.
+pub fn disk_uv(p: &Vector3<f64>, radius: f64) -> ((f64, f64), (Vector3<f64>, Vector3<f64>)) {
+    let distance = p.x.hypot(p.y);
+    let u = (p.y.atan2(p.x) / (2.0 * PI)).rem_euclid(1.0);
+    let v = distance / radius;
+
+    let tangent = Vector3::new(-p.y, p.x, 0.0);
+    let bitangent = Vector3::new(p.x, p.y, 0.0);
+    (
+        (u, v),
+        (
+            tangent.try_normalize(f64::EPSILON).unwrap_or_default(),
+            bitangent.try_normalize(f64::EPSILON).unwrap_or_default(),
+        ),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct Disk {
+    frame: Frame,
+    radius: f64,
+    material: Material,
+    bbox: Aabb,
+}
+
+impl Disk {
+    pub fn new(
+        center: Vector3<f64>,
+        normal: Vector3<f64>,
+        radius: f64,
+        material: Material,
+    ) -> Self {
+        let frame = Frame::new(center, normal);
+        let extent = frame.disk_extent(radius);
+        let bbox = Aabb::from_points(center - extent, center + extent);
+
+        Disk {
+            frame,
+            radius,
+            material,
+            bbox,
+        }
+    }
+
+    pub fn geometry(
+        center: Vector3<f64>,
+        normal: Vector3<f64>,
+        radius: f64,
+        material: Material,
+    ) -> Geometry {
+        Geometry::Disk(Disk::new(center, normal, radius, material))
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, interval: &Interval, record: &mut HitRecord, _: &mut ThreadRng) -> bool {
+        let local = self.frame.ray_to_local(r);
+        let Some(t) = intersect(&local, 0.0, self.radius) else {
+            return false;
+        };
+        if !interval.contains(t) {
+            return false;
+        }
+
+        let ((u, v), (tangent, bitangent)) = disk_uv(&local.at(t), self.radius);
+
+        record.t = t;
+        record.point = r.at(t);
+        record.set_face_normal(r, &self.frame.w);
+        record.material = self.material.clone();
+        (record.u, record.v) = (u, v);
+        record.tangent = self.frame.vector_to_world(&tangent);
+        record.bitangent = self.frame.vector_to_world(&bitangent);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+
+    fn material() -> Material {
+        Lambertian::material(SolidColor::texture(Vector3::from_element(0.5)))
+    }
+
+    fn hit(disk: &Disk, origin: [f64; 3], direction: [f64; 3]) -> Option<HitRecord> {
+        let ray = Ray::new(Vector3::from(origin), Vector3::from(direction), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut record = HitRecord::default();
+        disk.hit(&ray, &interval, &mut record, &mut rand::rng())
+            .then_some(record)
+    }
+
+    #[test]
+    fn test_disk_hits() {
+        let disk = Disk::new(Vector3::zeros(), Vector3::z(), 2.0, material());
+
+        let record = hit(&disk, [1.0, 0.0, 5.0], [0.0, 0.0, -1.0]).unwrap();
+        assert!((record.t - 5.0).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.normal - Vector3::z()).norm() < 1e-9);
+
+        let record = hit(&disk, [0.0, 1.0, -5.0], [0.0, 0.0, 1.0]).unwrap();
+        assert!(!record.front_face);
+
+        assert!(hit(&disk, [3.0, 0.0, 5.0], [0.0, 0.0, -1.0]).is_none());
+        assert!(hit(&disk, [1.0, 0.0, 5.0], [0.0, 0.0, 1.0]).is_none());
+        assert!(hit(&disk, [-5.0, 0.0, 0.0], [1.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_disk_uv() {
+        let disk = Disk::new(Vector3::zeros(), Vector3::z(), 2.0, material());
+
+        let record = hit(&disk, [1.0, 0.0, 5.0], [0.0, 0.0, -1.0]).unwrap();
+        assert!((record.u - 0.0).abs() < 1e-9 && (record.v - 0.5).abs() < 1e-9);
+
+        let record = hit(&disk, [0.0, 1.5, 5.0], [0.0, 0.0, -1.0]).unwrap();
+        assert!((record.u - 0.25).abs() < 1e-9 && (record.v - 0.75).abs() < 1e-9);
+        assert!((record.tangent - -Vector3::x()).norm() < 1e-9);
+        assert!((record.bitangent - Vector3::y()).norm() < 1e-9);
+    }
+}
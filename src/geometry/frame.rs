@@ -0,0 +1,94 @@
+use crate::ray::Ray;
+use nalgebra::Vector3;
+
+/// A right-handed orthonormal frame placed at `origin`, whose `w` axis points along a given
+/// direction. Shapes defined around an axis are intersected in the frame's local space.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub origin: Vector3<f64>,
+    pub u: Vector3<f64>,
+    pub v: Vector3<f64>,
+    pub w: Vector3<f64>,
+}
+
+impl Frame {
+    pub fn new(origin: Vector3<f64>, axis: Vector3<f64>) -> Self {
+        let w = axis.normalize();
+
+        // Duff et al., "Building an Orthonormal Basis, Revisited"
+        let sign = 1.0f64.copysign(w.z);
+        let a = -1.0 / (sign + w.z);
+        let b = w.x * w.y * a;
+        let u = Vector3::new(1.0 + sign * w.x * w.x * a, sign * b, -sign * w.x);
+        let v = Vector3::new(b, sign + w.y * w.y * a, -w.y);
+
+        Frame { origin, u, v, w }
+    }
+
+    pub fn to_local(&self, point: &Vector3<f64>) -> Vector3<f64> {
+        self.vector_to_local(&(point - self.origin))
+    }
+
+    pub fn vector_to_local(&self, vector: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::new(
+            vector.dot(&self.u),
+            vector.dot(&self.v),
+            vector.dot(&self.w),
+        )
+    }
+
+    pub fn vector_to_world(&self, vector: &Vector3<f64>) -> Vector3<f64> {
+        self.u * vector.x + self.v * vector.y + self.w * vector.z
+    }
+
+    /// The ray in local space, which reaches the same points at the same `t`.
+    pub fn ray_to_local(&self, r: &Ray) -> Ray {
+        Ray::new(
+            self.to_local(&r.origin),
+            self.vector_to_local(&r.direction),
+            r.time,
+        )
+    }
+
+    /// The half extents along each world axis of a disk of `radius` lying across the `w` axis.
+    pub fn disk_extent(&self, radius: f64) -> Vector3<f64> {
+        self.w.map(|n| radius * (1.0 - n * n).max(0.0).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_orthonormal() {
+        for axis in [
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Vector3::new(1.0, -2.0, 3.0),
+        ] {
+            let frame = Frame::new(Vector3::default(), axis);
+            assert!((frame.w - axis.normalize()).norm() < 1e-12);
+            assert!((frame.u.cross(&frame.v) - frame.w).norm() < 1e-12);
+            assert!(frame.u.dot(&frame.v).abs() < 1e-12);
+            assert!((frame.u.norm() - 1.0).abs() < 1e-12);
+            assert!((frame.v.norm() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frame = Frame::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(1.0, 1.0, 0.0));
+        let point = Vector3::new(-4.0, 5.0, 0.5);
+        let local = frame.to_local(&point);
+        let world = frame.vector_to_world(&local) + frame.origin;
+        assert!((world - point).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_frame_disk_extent() {
+        let frame = Frame::new(Vector3::default(), Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(frame.disk_extent(2.0), Vector3::new(2.0, 0.0, 2.0));
+    }
+}
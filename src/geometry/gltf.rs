@@ -0,0 +1,191 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::mesh::Face;
+use crate::geometry::mesh::Mesh;
+use crate::geometry::mesh::MeshData;
+use crate::geometry::opacity::Opacity;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use ::gltf::Node;
+use ::gltf::Scene;
+use ::gltf::buffer;
+use ::gltf::mesh::Mode;
+use nalgebra::Matrix4;
+use nalgebra::Point3;
+use nalgebra::Vector2;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+use std::error::Error;
+
+/// Walks every node of `scene`, pairing it with the transform from its local space into world
+/// space.
+pub fn scene_nodes<'a>(scene: &Scene<'a>) -> Vec<(Node<'a>, Matrix4<f64>)> {
+    fn visit<'a>(node: Node<'a>, parent: Matrix4<f64>, nodes: &mut Vec<(Node<'a>, Matrix4<f64>)>) {
+        let local = node.transform().matrix();
+        let world = parent * Matrix4::from_fn(|row, column| local[column][row] as f64);
+        for child in node.children() {
+            visit(child, world, nodes);
+        }
+        nodes.push((node, world));
+    }
+
+    let mut nodes = Vec::new();
+    for node in scene.nodes() {
+        visit(node, Matrix4::identity(), &mut nodes);
+    }
+    nodes
+}
+
+/// The set of texture coordinates the textures of `material` are read with. A face follows a
+/// single set, so every texture of the material has to share it.
+pub fn tex_coord_set(material: &::gltf::Material) -> Result<u32, Box<dyn Error>> {
+    let pbr = material.pbr_metallic_roughness();
+    let sets: Vec<u32> = [
+        pbr.base_color_texture(),
+        pbr.metallic_roughness_texture(),
+        material.emissive_texture(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|info| info.tex_coord())
+    .collect();
+    match sets.split_first() {
+        None => Ok(0),
+        Some((first, rest)) if rest.iter().all(|set| set == first) => Ok(*first),
+        Some(_) => {
+            Err("The textures of a glTF material must share one set of texture coordinates".into())
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Gltf {
+    mesh: Box<Geometry>,
+}
+
+impl Gltf {
+    /// Builds the triangles of every mesh in `scene`, placed by its node hierarchy. Primitives use
+    /// the entry of `materials` matching their material index, or `default` when they have none,
+    /// and `opacity` cuts holes into every mesh.
+    pub fn new(
+        scene: &Scene,
+        buffers: &[buffer::Data],
+        materials: &[Material],
+        default: &Material,
+        opacity: Option<Opacity>,
+    ) -> Self {
+        let mut data = MeshData {
+            materials: materials.iter().chain([default]).cloned().collect(),
+            opacity,
+            ..MeshData::default()
+        };
+
+        for (node, transform) in scene_nodes(scene) {
+            let normal_transform = transform
+                .fixed_view::<3, 3>(0, 0)
+                .try_inverse()
+                .unwrap_or_default()
+                .transpose();
+
+            if let Some(mesh) = node.mesh() {
+                for primitive in mesh.primitives() {
+                    if primitive.mode() != Mode::Triangles {
+                        continue;
+                    }
+
+                    let material = primitive.material().index().unwrap_or(materials.len()) as u32;
+
+                    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                    let Some(positions) = reader.read_positions() else {
+                        continue;
+                    };
+
+                    // Vertices of every primitive share buffers, so indices are offset by the
+                    // vertices added before them.
+                    let offset = data.positions.len() as u32;
+                    data.positions.extend(positions.map(|p| {
+                        let point = Point3::new(p[0] as f64, p[1] as f64, p[2] as f64);
+                        transform.transform_point(&point).coords
+                    }));
+                    let count = data.positions.len() as u32 - offset;
+
+                    let normal_offset = data.normals.len() as u32;
+                    let has_normals = match reader.read_normals() {
+                        Some(normals) => {
+                            data.normals.extend(normals.map(|n| {
+                                (normal_transform
+                                    * Vector3::new(n[0] as f64, n[1] as f64, n[2] as f64))
+                                .normalize()
+                            }));
+                            true
+                        }
+                        None => false,
+                    };
+
+                    let uv_offset = data.uvs.len() as u32;
+                    // Materials overriding the file's ignore its textures, so a clash between
+                    // their sets does not matter.
+                    let set = tex_coord_set(&primitive.material()).unwrap_or(0);
+                    let has_uvs = match reader.read_tex_coords(set) {
+                        Some(uvs) => {
+                            data.uvs.extend(
+                                uvs.into_f32()
+                                    .map(|uv| Vector2::new(uv[0] as f64, uv[1] as f64)),
+                            );
+                            true
+                        }
+                        None => false,
+                    };
+
+                    let indices: Vec<u32> = match reader.read_indices() {
+                        Some(indices) => indices.into_u32().collect(),
+                        None => (0..count).collect(),
+                    };
+
+                    for face in indices.chunks_exact(3) {
+                        let corners = [face[0], face[1], face[2]];
+                        data.faces.push(Face {
+                            positions: corners.map(|i| i + offset),
+                            normals: has_normals.then(|| corners.map(|i| i + normal_offset)),
+                            uvs: has_uvs.then(|| corners.map(|i| i + uv_offset)),
+                            material,
+                        });
+                    }
+                }
+            }
+        }
+
+        Gltf {
+            mesh: Box::new(Mesh::geometry(data)),
+        }
+    }
+
+    pub fn geometry(
+        scene: &Scene,
+        buffers: &[buffer::Data],
+        materials: &[Material],
+        default: &Material,
+        opacity: Option<Opacity>,
+    ) -> Geometry {
+        Geometry::Gltf(Gltf::new(scene, buffers, materials, default, opacity))
+    }
+}
+
+impl Hittable for Gltf {
+    fn hit(
+        &self,
+        r: &Ray,
+        interval: &Interval,
+        record: &mut HitRecord,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        self.mesh.hit(r, interval, record, rng)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.mesh.bounding_box()
+    }
+}
@@ -0,0 +1,487 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::opacity::Opacity;
+use crate::geometry::triangle::intersect;
+use crate::geometry::triangle::uv_tangents;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::material::lambertian::Lambertian;
+use crate::material::texture::Image;
+use crate::material::texture::SolidColor;
+use crate::ray::Ray;
+use nalgebra::Vector2;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const MAX_LEAF_FACES: usize = 4;
+
+/// A triangle of a mesh, indexing into the mesh's shared buffers.
+#[derive(Debug, Clone)]
+pub struct Face {
+    pub positions: [u32; 3],
+    pub normals: Option<[u32; 3]>,
+    pub uvs: Option<[u32; 3]>,
+    pub material: u32,
+}
+
+/// The buffers a mesh is built from. `colors`, when not empty, are indexed like `positions` and
+/// give every face a diffuse material blending the colors of its corners in place of
+/// `materials`. `normal_map` is a tangent-space normal map perturbing the shading normal of faces
+/// with texture coordinates, and `opacity` cuts holes into every face.
+#[derive(Debug, Clone, Default)]
+pub struct MeshData {
+    pub positions: Vec<Vector3<f64>>,
+    pub normals: Vec<Vector3<f64>>,
+    pub uvs: Vec<Vector2<f64>>,
+    pub colors: Vec<Vector3<f64>>,
+    pub faces: Vec<Face>,
+    pub materials: Vec<Material>,
+    pub normal_map: Option<Image>,
+    pub opacity: Option<Opacity>,
+}
+
+impl MeshData {
+    /// Replaces the normals of every face with vertex normals averaged from the faces sharing
+    /// each vertex, weighted by the angle of their corner. Faces only blend with neighbours whose
+    /// normals lie within `crease_angle` degrees of their own, keeping sharper edges flat.
+    pub fn smooth_normals(&mut self, crease_angle: f64) {
+        let min_cos = crease_angle.to_radians().cos();
+
+        let face_normals: Vec<Vector3<f64>> = self
+            .faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = face.positions.map(|i| self.positions[i as usize]);
+                (b - a)
+                    .cross(&(c - a))
+                    .try_normalize(0.0)
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let mut corners: Vec<Vec<(usize, f64)>> = vec![Vec::new(); self.positions.len()];
+        for (index, face) in self.faces.iter().enumerate() {
+            let points = face.positions.map(|i| self.positions[i as usize]);
+            for corner in 0..3 {
+                let to_next = points[(corner + 1) % 3] - points[corner];
+                let to_prev = points[(corner + 2) % 3] - points[corner];
+                let weight = to_next.angle(&to_prev);
+                if weight.is_finite() {
+                    corners[face.positions[corner] as usize].push((index, weight));
+                }
+            }
+        }
+
+        // Corners blending the same neighbours share a normal, so identical ones are merged.
+        let mut normals = Vec::new();
+        let mut indices: HashMap<(u32, [u64; 3]), u32> = HashMap::new();
+        for (index, face) in self.faces.iter_mut().enumerate() {
+            let face_normal = face_normals[index];
+            let normal_indices = face.positions.map(|position| {
+                let normal = corners[position as usize]
+                    .iter()
+                    .filter(|(other, _)| face_normals[*other].dot(&face_normal) >= min_cos)
+                    .map(|(other, weight)| face_normals[*other] * *weight)
+                    .sum::<Vector3<f64>>()
+                    .try_normalize(0.0)
+                    .unwrap_or(face_normal);
+
+                *indices
+                    .entry((position, normal.map(f64::to_bits).into()))
+                    .or_insert_with(|| {
+                        normals.push(normal);
+                        normals.len() as u32 - 1
+                    })
+            });
+            face.normals = Some(normal_indices);
+        }
+
+        self.normals = normals;
+    }
+
+    fn face_bbox(&self, face: &Face) -> Aabb {
+        let [a, b, c] = face.positions.map(|i| self.positions[i as usize]);
+        Aabb::from_boxes(&Aabb::from_points(a, b), &Aabb::from_points(c, c))
+    }
+
+    fn face_centroid(&self, face: &Face) -> Vector3<f64> {
+        face.positions
+            .iter()
+            .map(|i| self.positions[*i as usize])
+            .sum::<Vector3<f64>>()
+            / 3.0
+    }
+}
+
+/// A node of the mesh's flattened BVH. Leaves hold `count` faces starting at `start`, interior
+/// nodes are followed by their left child and hold the index of their right child in `start`.
+#[derive(Debug, Clone)]
+struct MeshNode {
+    bbox: Aabb,
+    start: u32,
+    count: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    data: Arc<MeshData>,
+    nodes: Arc<[MeshNode]>,
+}
+
+impl Mesh {
+    pub fn new(data: MeshData) -> Self {
+        let mut data = data;
+        let mut faces = std::mem::take(&mut data.faces);
+        let mut nodes = Vec::with_capacity(2 * faces.len() / MAX_LEAF_FACES + 1);
+        Mesh::build(&data, &mut faces, 0, &mut nodes);
+        data.faces = faces;
+
+        Mesh {
+            data: Arc::new(data),
+            nodes: nodes.into(),
+        }
+    }
+
+    pub fn geometry(data: MeshData) -> Geometry {
+        Geometry::Mesh(Mesh::new(data))
+    }
+
+    fn build(data: &MeshData, faces: &mut [Face], start: usize, nodes: &mut Vec<MeshNode>) {
+        let bbox = faces.iter().fold(Aabb::default(), |bbox, face| {
+            Aabb::from_boxes(&bbox, &data.face_bbox(face))
+        });
+
+        let index = nodes.len();
+        nodes.push(MeshNode {
+            bbox,
+            start: start as u32,
+            count: faces.len() as u32,
+        });
+
+        if faces.len() <= MAX_LEAF_FACES {
+            return;
+        }
+
+        let centroids = faces.iter().fold(Aabb::default(), |bbox, face| {
+            let centroid = data.face_centroid(face);
+            Aabb::from_boxes(&bbox, &Aabb::from_points(centroid, centroid))
+        });
+        let axis = centroids.longest_axis().as_index();
+
+        let mid = faces.len() / 2;
+        faces.select_nth_unstable_by(mid, |a, b| {
+            data.face_centroid(a)[axis].total_cmp(&data.face_centroid(b)[axis])
+        });
+        let (left, right) = faces.split_at_mut(mid);
+
+        Mesh::build(data, left, start, nodes);
+        nodes[index].start = nodes.len() as u32;
+        nodes[index].count = 0;
+        Mesh::build(data, right, start + mid, nodes);
+    }
+
+    fn hit_face(
+        &self,
+        r: &Ray,
+        face: &Face,
+        interval: &Interval,
+        record: &mut HitRecord,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        let data = &self.data;
+        let [a, b, c] = face.positions.map(|i| data.positions[i as usize]);
+
+        let Some((t, u, v)) = intersect(r, &a, &b, &c) else {
+            return false;
+        };
+        if !interval.contains(t) {
+            return false;
+        }
+
+        let uvs = face.uvs.map(|uvs| uvs.map(|i| data.uvs[i as usize]));
+        let uv = match uvs {
+            Some(uvs) => (1.0 - u - v) * uvs[0] + u * uvs[1] + v * uvs[2],
+            None => Vector2::new(u, v),
+        };
+        let point = r.at(t);
+        if let Some(opacity) = &data.opacity
+            && !opacity.is_opaque(uv.x, uv.y, point, rng)
+        {
+            return false;
+        }
+
+        let outward_normal = match face.normals {
+            Some(normals) => {
+                let [na, nb, nc] = normals.map(|i| data.normals[i as usize]);
+                ((1.0 - u - v) * na + u * nb + v * nc).normalize()
+            }
+            None => (b - a).cross(&(c - a)).normalize(),
+        };
+
+        record.t = t;
+        record.point = point;
+        record.set_face_normal(r, &outward_normal);
+        (record.u, record.v) = (uv.x, uv.y);
+        let (tangent, bitangent) = match uvs {
+            Some(uvs) => {
+                let tangents = uv_tangents([a, b, c], uvs);
+                if let (Some(normal_map), Some(tangents)) = (&data.normal_map, tangents) {
+                    let mapped = map_normal(normal_map, &outward_normal, tangents, uv);
+                    record.normal = if record.front_face { mapped } else { -mapped };
+                }
+
+                tangents.unwrap_or_default()
+            }
+            None => (b - a, c - a),
+        };
+        record.tangent = tangent.try_normalize(0.0).unwrap_or_default();
+        record.bitangent = bitangent.try_normalize(0.0).unwrap_or_default();
+        record.material = match data.colors.is_empty() {
+            true => data.materials[face.material as usize].clone(),
+            false => {
+                let [ca, cb, cc] = face.positions.map(|i| data.colors[i as usize]);
+                Lambertian::material(SolidColor::texture((1.0 - u - v) * ca + u * cb + v * cc))
+            }
+        };
+
+        true
+    }
+}
+
+/// Perturbs `normal` by the tangent-space `normal_map` at `uv`, with the tangent frame following
+/// the texture coordinates across the triangle.
+fn map_normal(
+    normal_map: &Image,
+    normal: &Vector3<f64>,
+    (tangent, bitangent): (Vector3<f64>, Vector3<f64>),
+    uv: Vector2<f64>,
+) -> Vector3<f64> {
+    let Some(tangent) = (tangent - normal * normal.dot(&tangent)).try_normalize(f64::EPSILON)
+    else {
+        return *normal;
+    };
+    // Texture coordinates have v = 0 at the top, normal maps point green towards v = 0 at the
+    // bottom, so the bitangent is flipped.
+    let handedness = if normal.cross(&tangent).dot(&bitangent) < 0.0 {
+        1.0
+    } else {
+        -1.0
+    };
+    let bitangent = normal.cross(&tangent) * handedness;
+
+    let texel = normal_map.texel(uv.x, uv.y) * 2.0 - Vector3::from_element(1.0);
+    (tangent * texel.x + bitangent * texel.y + normal * texel.z)
+        .try_normalize(f64::EPSILON)
+        .unwrap_or(*normal)
+}
+
+impl Hittable for Mesh {
+    fn hit(
+        &self,
+        r: &Ray,
+        interval: &Interval,
+        record: &mut HitRecord,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        if self.data.faces.is_empty() {
+            return false;
+        }
+
+        let mut closest = *interval;
+        let mut hit_anything = false;
+
+        // Median splits keep the tree balanced, so its depth stays far below the stack size.
+        let mut stack = [0usize; 64];
+        let mut stack_len = 1;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let index = stack[stack_len];
+            let node = &self.nodes[index];
+            if !node.bbox.hit(r, &closest) {
+                continue;
+            }
+
+            if node.count == 0 {
+                debug_assert!(stack_len + 2 <= stack.len(), "The mesh BVH is too deep");
+                stack[stack_len] = node.start as usize;
+                stack[stack_len + 1] = index + 1;
+                stack_len += 2;
+                continue;
+            }
+
+            let start = node.start as usize;
+            for face in &self.data.faces[start..start + node.count as usize] {
+                if self.hit_face(r, face, &closest, record, rng) {
+                    hit_anything = true;
+                    closest.max = record.t;
+                }
+            }
+        }
+
+        hit_anything
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.nodes[0].bbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn random_point(rng: &mut ChaCha8Rng, size: f64) -> Vector3<f64> {
+        Vector3::from_fn(|_, _| rng.random_range(-size..size))
+    }
+
+    /// A mesh of `positions` and the triangles of `faces`, wound counterclockwise from outside.
+    fn mesh_data(positions: &[[f64; 3]], faces: &[[u32; 3]]) -> MeshData {
+        MeshData {
+            positions: positions.iter().map(|p| Vector3::from(*p)).collect(),
+            faces: faces
+                .iter()
+                .map(|face| Face {
+                    positions: *face,
+                    normals: None,
+                    uvs: None,
+                    material: 0,
+                })
+                .collect(),
+            ..MeshData::default()
+        }
+    }
+
+    /// The normals of the corners of every face.
+    fn corner_normals(data: &MeshData) -> Vec<[Vector3<f64>; 3]> {
+        data.faces
+            .iter()
+            .map(|face| face.normals.unwrap().map(|i| data.normals[i as usize]))
+            .collect()
+    }
+
+    #[test]
+    fn test_crease_keeps_cube_flat() {
+        let positions: Vec<[f64; 3]> = (0..8)
+            .map(|i| [i & 1, (i >> 1) & 1, (i >> 2) & 1].map(|c| c as f64))
+            .collect();
+        let faces = [
+            [0, 2, 1],
+            [1, 2, 3],
+            [4, 5, 6],
+            [5, 7, 6],
+            [0, 1, 4],
+            [1, 5, 4],
+            [2, 6, 3],
+            [3, 6, 7],
+            [0, 4, 2],
+            [2, 4, 6],
+            [1, 3, 5],
+            [3, 7, 5],
+        ];
+        let mut data = mesh_data(&positions, &faces);
+        data.smooth_normals(30.0);
+
+        for (face, normals) in data.faces.iter().zip(corner_normals(&data)) {
+            let [a, b, c] = face.positions.map(|i| data.positions[i as usize]);
+            let flat = (b - a).cross(&(c - a)).normalize();
+            assert!(normals.iter().all(|normal| (normal - flat).norm() < 1e-9));
+        }
+        // Each corner of the cube is shared by three sides, and faces of the same side share
+        // their normals.
+        assert_eq!(data.normals.len(), 24);
+    }
+
+    #[test]
+    fn test_smooth_octahedron() {
+        let positions = [
+            [1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+        ];
+        let faces = [
+            [0, 2, 4],
+            [2, 1, 4],
+            [1, 3, 4],
+            [3, 0, 4],
+            [2, 0, 5],
+            [1, 2, 5],
+            [3, 1, 5],
+            [0, 3, 5],
+        ];
+        let mut data = mesh_data(&positions, &faces);
+        data.smooth_normals(180.0);
+
+        // Averaged around each corner, the normals point straight out of it, like a sphere's.
+        for (face, normals) in data.faces.iter().zip(corner_normals(&data)) {
+            for (position, normal) in face.positions.iter().zip(normals) {
+                assert!((normal - data.positions[*position as usize]).norm() < 1e-9);
+            }
+        }
+        assert_eq!(data.normals.len(), 6);
+
+        // Below the angle between neighbouring faces, no corners are merged.
+        let mut data = mesh_data(&positions, &faces);
+        data.smooth_normals(60.0);
+        assert_eq!(data.normals.len(), 24);
+    }
+
+    #[test]
+    fn test_hits_match_brute_force() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        let mut data = MeshData {
+            materials: vec![Lambertian::material(SolidColor::texture(
+                Vector3::from_element(0.5),
+            ))],
+            ..MeshData::default()
+        };
+        for i in 0..500 {
+            let center = random_point(&mut rng, 10.0);
+            data.positions
+                .extend((0..3).map(|_| center + random_point(&mut rng, 1.0)));
+            data.faces.push(Face {
+                positions: [3 * i, 3 * i + 1, 3 * i + 2],
+                normals: None,
+                uvs: None,
+                material: 0,
+            });
+        }
+        let mesh = Mesh::new(data.clone());
+
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let origin = random_point(&mut rng, 15.0);
+            let target = random_point(&mut rng, 10.0);
+            let ray = Ray::new(origin, target - origin, 0.0);
+
+            let closest = data
+                .faces
+                .iter()
+                .filter_map(|face| {
+                    let [a, b, c] = face.positions.map(|i| data.positions[i as usize]);
+                    intersect(&ray, &a, &b, &c).map(|(t, _, _)| t)
+                })
+                .filter(|t| interval.contains(*t))
+                .min_by(f64::total_cmp);
+
+            let mut record = HitRecord::default();
+            let hit = mesh.hit(&ray, &interval, &mut record, &mut rand::rng());
+            assert_eq!(hit, closest.is_some());
+            if let Some(t) = closest {
+                assert!((record.t - t).abs() < 1e-9, "{} != {}", record.t, t);
+                hits += 1;
+            }
+        }
+        assert!(hits > 100, "Too few rays hit the mesh to compare");
+    }
+}
@@ -1,29 +1,52 @@
 pub mod aabb;
+pub mod atmosphere;
 pub mod axis;
+pub mod bump;
 pub mod bvh;
+pub mod cone;
+pub mod csg;
 pub mod cube;
+pub mod cylinder;
+pub mod disk;
 pub mod empty;
+pub mod frame;
+pub mod gltf;
+pub mod mesh;
+pub mod opacity;
+pub mod plane;
 pub mod quad;
 pub mod rotate;
 pub mod scale;
+pub mod sdf;
 pub mod sphere;
+pub mod torus;
 pub mod translate;
 pub mod triangle;
 pub mod volume;
 pub mod wavefront;
 
 use crate::geometry::aabb::Aabb;
+use crate::geometry::atmosphere::Atmosphere;
+use crate::geometry::bump::Bump;
 use crate::geometry::bvh::BvhNode;
+use crate::geometry::cone::Cone;
+use crate::geometry::csg::Csg;
 use crate::geometry::cube::Cube;
+use crate::geometry::cylinder::Cylinder;
+use crate::geometry::disk::Disk;
 use crate::geometry::empty::Empty;
+use crate::geometry::gltf::Gltf;
+use crate::geometry::mesh::Mesh;
+use crate::geometry::plane::Plane;
 use crate::geometry::quad::Quad;
 use crate::geometry::rotate::Rotate;
 use crate::geometry::scale::Scale;
+use crate::geometry::sdf::Sdf;
 use crate::geometry::sphere::Sphere;
+use crate::geometry::torus::Torus;
 use crate::geometry::translate::Translate;
 use crate::geometry::triangle::Triangle;
 use crate::geometry::volume::Volume;
-use crate::geometry::wavefront::Wavefront;
 use crate::interval::Interval;
 use crate::material::Material;
 use crate::material::lambertian::Lambertian;
@@ -54,8 +77,18 @@ pub enum Geometry {
     Rotate(Rotate),
     Volume(Volume),
     Triangle(Triangle),
-    Wavefront(Wavefront),
     Scale(Scale),
+    Gltf(Gltf),
+    Mesh(Mesh),
+    Bump(Bump),
+    Disk(Disk),
+    Cylinder(Cylinder),
+    Cone(Cone),
+    Torus(Torus),
+    Plane(Plane),
+    Csg(Csg),
+    Sdf(Sdf),
+    Atmosphere(Atmosphere),
 }
 
 impl Hittable for Geometry {
@@ -76,8 +109,18 @@ impl Hittable for Geometry {
             Geometry::Rotate(geometry) => geometry.hit(ray, interval, record, rng),
             Geometry::Volume(geometry) => geometry.hit(ray, interval, record, rng),
             Geometry::Triangle(geometry) => geometry.hit(ray, interval, record, rng),
-            Geometry::Wavefront(geometry) => geometry.hit(ray, interval, record, rng),
             Geometry::Scale(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Gltf(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Mesh(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Bump(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Disk(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Cylinder(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Cone(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Torus(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Plane(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Csg(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Sdf(geometry) => geometry.hit(ray, interval, record, rng),
+            Geometry::Atmosphere(geometry) => geometry.hit(ray, interval, record, rng),
         }
     }
 
@@ -92,12 +135,23 @@ impl Hittable for Geometry {
             Geometry::Rotate(geometry) => geometry.bounding_box(),
             Geometry::Volume(geometry) => geometry.bounding_box(),
             Geometry::Triangle(geometry) => geometry.bounding_box(),
-            Geometry::Wavefront(geometry) => geometry.bounding_box(),
             Geometry::Scale(geometry) => geometry.bounding_box(),
+            Geometry::Gltf(geometry) => geometry.bounding_box(),
+            Geometry::Mesh(geometry) => geometry.bounding_box(),
+            Geometry::Bump(geometry) => geometry.bounding_box(),
+            Geometry::Disk(geometry) => geometry.bounding_box(),
+            Geometry::Cylinder(geometry) => geometry.bounding_box(),
+            Geometry::Cone(geometry) => geometry.bounding_box(),
+            Geometry::Torus(geometry) => geometry.bounding_box(),
+            Geometry::Plane(geometry) => geometry.bounding_box(),
+            Geometry::Csg(geometry) => geometry.bounding_box(),
+            Geometry::Sdf(geometry) => geometry.bounding_box(),
+            Geometry::Atmosphere(geometry) => geometry.bounding_box(),
         }
     }
 }
 
+#[derive(Clone)]
 pub struct HitRecord {
     pub point: Vector3<f64>,
     pub normal: Vector3<f64>,
@@ -106,6 +160,10 @@ pub struct HitRecord {
     pub material: Material,
     pub u: f64,
     pub v: f64,
+    /// The unit directions along the surface in which `u` and `v` increase, or zero where the
+    /// surface has no such direction.
+    pub tangent: Vector3<f64>,
+    pub bitangent: Vector3<f64>,
 }
 
 impl HitRecord {
@@ -127,6 +185,8 @@ impl HitRecord {
             material: Lambertian::material(SolidColor::texture(Vector3::new(0.98, 0.75, 0.79))),
             u: 0.0,
             v: 0.0,
+            tangent: Vector3::default(),
+            bitangent: Vector3::default(),
         }
     }
 }
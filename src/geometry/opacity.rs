@@ -0,0 +1,28 @@
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use nalgebra::Vector3;
+use rand::Rng;
+
+/// A mask cutting holes into a surface, such as the outline of a leaf on a quad. Where the
+/// `texture` is below the `threshold` rays pass through; without a threshold they pass through
+/// at random as often as the surface is transparent, so it looks partly see-through.
+#[derive(Debug, Clone)]
+pub struct Opacity {
+    pub texture: Texture,
+    pub threshold: Option<f64>,
+}
+
+impl Opacity {
+    pub fn new(texture: Texture, threshold: Option<f64>) -> Self {
+        Opacity { texture, threshold }
+    }
+
+    /// Whether a ray hitting the surface at `u`, `v`, `p` stops there.
+    pub fn is_opaque<T: Rng>(&self, u: f64, v: f64, p: Vector3<f64>, rng: &mut T) -> bool {
+        let opacity = self.texture.sample(u, v, p).mean();
+        match self.threshold {
+            Some(threshold) => opacity >= threshold,
+            None => opacity > rng.random::<f64>(),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::frame::Frame;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+
+/// How far the bounding box of a plane reaches. Infinite bounds would turn into NaN once rotated.
+const EXTENT: f64 = 1e12;
+
+/// An infinite plane through `point` facing `normal`. Its texture coordinates measure the
+/// distance from `point` along the plane, so textures repeat across it.
+#[derive(Debug, Clone)]
+pub struct Plane {
+    frame: Frame,
+    material: Material,
+    bbox: Aabb,
+}
+
+impl Plane {
+    pub fn new(point: Vector3<f64>, normal: Vector3<f64>, material: Material) -> Self {
+        let frame = Frame::new(point, normal);
+
+        // Planes facing along an axis are flat along it, every other direction is unbounded.
+        let mut extent = Vector3::from_element(EXTENT);
+        if let Some(axis) = (0..3).find(|axis| frame.w[*axis].abs() == 1.0) {
+            extent[axis] = 0.0;
+        }
+        let bbox = Aabb::from_points(point - extent, point + extent);
+
+        Plane {
+            frame,
+            material,
+            bbox,
+        }
+    }
+
+    pub fn geometry(point: Vector3<f64>, normal: Vector3<f64>, material: Material) -> Geometry {
+        Geometry::Plane(Plane::new(point, normal, material))
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, r: &Ray, interval: &Interval, record: &mut HitRecord, _: &mut ThreadRng) -> bool {
+        let local = self.frame.ray_to_local(r);
+        if local.direction.z.abs() < 1e-8 {
+            return false; // Ray is parallel to the plane
+        }
+
+        let t = -local.origin.z / local.direction.z;
+        if !interval.contains(t) {
+            return false;
+        }
+
+        let p = local.at(t);
+
+        record.t = t;
+        record.point = r.at(t);
+        record.set_face_normal(r, &self.frame.w);
+        record.material = self.material.clone();
+        (record.u, record.v) = (p.x, p.y);
+        record.tangent = self.frame.u;
+        record.bitangent = self.frame.v;
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+
+    fn material() -> Material {
+        Lambertian::material(SolidColor::texture(Vector3::from_element(0.5)))
+    }
+
+    fn hit(plane: &Plane, origin: [f64; 3], direction: [f64; 3]) -> Option<HitRecord> {
+        let ray = Ray::new(Vector3::from(origin), Vector3::from(direction), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut record = HitRecord::default();
+        plane
+            .hit(&ray, &interval, &mut record, &mut rand::rng())
+            .then_some(record)
+    }
+
+    #[test]
+    fn test_plane_hits() {
+        let plane = Plane::new(Vector3::zeros(), Vector3::z(), material());
+
+        let record = hit(&plane, [0.0, 0.0, 1.0], [1.0, 1.0, -1.0]).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.point - Vector3::new(1.0, 1.0, 0.0)).norm() < 1e-9);
+
+        let record = hit(&plane, [0.0, 0.0, -1.0], [0.0, 0.0, 1.0]).unwrap();
+        assert!(!record.front_face);
+        assert!((record.normal - -Vector3::z()).norm() < 1e-9);
+
+        assert!(hit(&plane, [0.0, 0.0, 1.0], [0.0, 0.0, 1.0]).is_none());
+        assert!(hit(&plane, [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_plane_uv() {
+        let plane = Plane::new(Vector3::new(1.0, 1.0, 0.0), Vector3::z(), material());
+
+        let record = hit(&plane, [4.0, -2.0, 3.0], [0.0, 0.0, -1.0]).unwrap();
+        assert!((record.u - 3.0).abs() < 1e-9 && (record.v - -3.0).abs() < 1e-9);
+        assert!((record.tangent - Vector3::x()).norm() < 1e-9);
+        assert!((record.bitangent - Vector3::y()).norm() < 1e-9);
+    }
+}
@@ -2,6 +2,7 @@ use crate::geometry::Geometry;
 use crate::geometry::HitRecord;
 use crate::geometry::Hittable;
 use crate::geometry::aabb::Aabb;
+use crate::geometry::opacity::Opacity;
 use crate::interval::Interval;
 use crate::material::Material;
 use crate::ray::Ray;
@@ -18,10 +19,17 @@ pub struct Quad {
     pub normal: Vector3<f64>,
     pub d: f64,
     pub w: Vector3<f64>,
+    pub opacity: Option<Opacity>,
 }
 
 impl Quad {
-    pub fn new(q: Vector3<f64>, u: Vector3<f64>, v: Vector3<f64>, material: Material) -> Self {
+    pub fn new(
+        q: Vector3<f64>,
+        u: Vector3<f64>,
+        v: Vector3<f64>,
+        material: Material,
+        opacity: Option<Opacity>,
+    ) -> Self {
         let n = u.cross(&v);
         let normal = n.normalize();
         let d = normal.dot(&q);
@@ -39,6 +47,7 @@ impl Quad {
             normal,
             d,
             w,
+            opacity,
         }
     }
     pub fn geometry(
@@ -46,25 +55,25 @@ impl Quad {
         u: Vector3<f64>,
         v: Vector3<f64>,
         material: Material,
+        opacity: Option<Opacity>,
     ) -> Geometry {
-        Geometry::Quad(Quad::new(q, u, v, material))
+        Geometry::Quad(Quad::new(q, u, v, material, opacity))
     }
 }
 
-fn is_interior(a: f64, b: f64, record: &mut HitRecord) -> bool {
+fn is_interior(a: f64, b: f64) -> bool {
     let unit_interval = Interval::new(0.0, 1.0);
-
-    if !unit_interval.contains(a) || !unit_interval.contains(b) {
-        return false;
-    }
-
-    record.u = a;
-    record.v = b;
-    true
+    unit_interval.contains(a) && unit_interval.contains(b)
 }
 
 impl Hittable for Quad {
-    fn hit(&self, r: &Ray, interval: &Interval, record: &mut HitRecord, _: &mut ThreadRng) -> bool {
+    fn hit(
+        &self,
+        r: &Ray,
+        interval: &Interval,
+        record: &mut HitRecord,
+        rng: &mut ThreadRng,
+    ) -> bool {
         let denom = self.normal.dot(&r.direction);
         if denom.abs() < 1e-8 {
             return false;
@@ -82,14 +91,23 @@ impl Hittable for Quad {
         let alpha = self.w.dot(&planar_hitpt_vector.cross(&self.v));
         let beta = self.w.dot(&self.u.cross(&planar_hitpt_vector));
 
-        if !is_interior(alpha, beta, record) {
+        if !is_interior(alpha, beta) {
+            return false;
+        }
+
+        if let Some(opacity) = &self.opacity
+            && !opacity.is_opaque(alpha, beta, intersection, rng)
+        {
             return false;
         }
 
         record.t = t;
         record.point = intersection;
+        (record.u, record.v) = (alpha, beta);
         record.material = self.material.clone();
         record.set_face_normal(r, &self.normal);
+        record.tangent = self.u.normalize();
+        record.bitangent = self.v.normalize();
 
         true
     }
@@ -98,3 +116,45 @@ impl Hittable for Quad {
         self.bbox.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+
+    fn masked(opacity: f64) -> Quad {
+        let mask = SolidColor::texture(Vector3::from_element(opacity));
+        Quad::new(
+            Vector3::new(-1.0, -1.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Lambertian::material(SolidColor::texture(Vector3::from_element(0.5))),
+            Some(Opacity::new(mask, Some(0.5))),
+        )
+    }
+
+    fn hits(quad: &Quad, origin: Vector3<f64>) -> bool {
+        let ray = Ray::new(origin, -Vector3::z(), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        quad.hit(&ray, &interval, &mut HitRecord::default(), &mut rand::rng())
+    }
+
+    #[test]
+    fn test_threshold_mask() {
+        let center = Vector3::new(0.0, 0.0, 1.0);
+        assert!(!hits(&masked(0.3), center));
+        assert!(hits(&masked(0.7), center));
+        assert!(!hits(&masked(0.7), Vector3::new(2.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_masked_miss_keeps_record() {
+        let mut record = HitRecord::default();
+        (record.u, record.v) = (0.25, 0.75);
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 1.0), -Vector3::z(), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        assert!(!masked(0.3).hit(&ray, &interval, &mut record, &mut rand::rng()));
+        assert_eq!((record.u, record.v), (0.25, 0.75));
+    }
+}
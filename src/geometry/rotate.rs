@@ -77,6 +77,8 @@ impl Hittable for Rotate {
         // Transform the intersection from object space back to world space.
         record.point = self.rotation * record.point;
         record.normal = self.rotation * record.normal;
+        record.tangent = self.rotation * record.tangent;
+        record.bitangent = self.rotation * record.bitangent;
         true
     }
 
@@ -49,6 +49,16 @@ impl Hittable for Scale {
 
         record.point = record.point.component_mul(&self.scale);
         record.normal = record.normal.component_div(&self.scale).normalize();
+        record.tangent = record
+            .tangent
+            .component_mul(&self.scale)
+            .try_normalize(0.0)
+            .unwrap_or_default();
+        record.bitangent = record
+            .bitangent
+            .component_mul(&self.scale)
+            .try_normalize(0.0)
+            .unwrap_or_default();
         true
     }
 
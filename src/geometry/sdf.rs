@@ -0,0 +1,434 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use nalgebra::Vector2;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+
+/// Steps taken along a ray before it is considered to have missed.
+const MAX_STEPS: usize = 512;
+
+/// Distance below which a ray is considered to have reached the surface.
+const EPSILON: f64 = 1e-5;
+
+/// Offset used to estimate the gradient of the distance at a hit.
+const GRADIENT_DELTA: f64 = 1e-5;
+
+/// A signed distance function, negative inside the shape and positive outside of it.
+#[derive(Debug, Clone)]
+pub enum Distance {
+    Sphere {
+        center: Vector3<f64>,
+        radius: f64,
+    },
+    /// A box of `half_size` around `center`, with its edges rounded by `radius`.
+    Box {
+        center: Vector3<f64>,
+        half_size: Vector3<f64>,
+        radius: f64,
+    },
+    /// A torus lying flat across the y axis.
+    Torus {
+        center: Vector3<f64>,
+        major_radius: f64,
+        minor_radius: f64,
+    },
+    /// Blends two shapes over a distance of `smoothness`.
+    SmoothUnion {
+        a: Box<Distance>,
+        b: Box<Distance>,
+        smoothness: f64,
+    },
+    /// Carves `b` out of `a`, rounding the cut over a distance of `smoothness`.
+    SmoothSubtract {
+        a: Box<Distance>,
+        b: Box<Distance>,
+        smoothness: f64,
+    },
+    /// Copies of a shape spaced by `period`, with `count` copies on each side of the original.
+    Repeat {
+        shape: Box<Distance>,
+        period: Vector3<f64>,
+        count: Vector3<f64>,
+    },
+    /// Twists a shape around the y axis by `rate` radians per unit of height.
+    Twist {
+        shape: Box<Distance>,
+        rate: f64,
+    },
+}
+
+/// Polynomial smooth minimum of two distances.
+fn smooth_min(a: f64, b: f64, smoothness: f64) -> f64 {
+    if smoothness <= 0.0 {
+        return a.min(b);
+    }
+    let h = (smoothness - (a - b).abs()).max(0.0) / smoothness;
+    a.min(b) - h * h * smoothness / 4.0
+}
+
+impl Distance {
+    pub fn distance(&self, p: &Vector3<f64>) -> f64 {
+        match self {
+            Distance::Sphere { center, radius } => (p - center).norm() - radius,
+            Distance::Box {
+                center,
+                half_size,
+                radius,
+            } => {
+                let q = (p - center).abs() - half_size + Vector3::from_element(*radius);
+                q.sup(&Vector3::zeros()).norm() + q.max().min(0.0) - radius
+            }
+            Distance::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => {
+                let p = p - center;
+                let q = Vector2::new(p.x.hypot(p.z) - major_radius, p.y);
+                q.norm() - minor_radius
+            }
+            Distance::SmoothUnion { a, b, smoothness } => {
+                smooth_min(a.distance(p), b.distance(p), *smoothness)
+            }
+            Distance::SmoothSubtract { a, b, smoothness } => {
+                -smooth_min(-a.distance(p), b.distance(p), *smoothness)
+            }
+            Distance::Repeat {
+                shape,
+                period,
+                count,
+            } => {
+                let cell = p
+                    .component_div(period)
+                    .map(f64::round)
+                    .zip_map(count, |cell, count| cell.clamp(-count, count));
+                shape.distance(&(p - period.component_mul(&cell)))
+            }
+            Distance::Twist { shape, rate } => {
+                let (sin, cos) = (rate * p.y).sin_cos();
+                let twisted = Vector3::new(cos * p.x - sin * p.z, p.y, sin * p.x + cos * p.z);
+                shape.distance(&twisted)
+            }
+        }
+    }
+
+    /// A box holding every point of the shape.
+    pub fn bounds(&self) -> Aabb {
+        match self {
+            Distance::Sphere { center, radius } => {
+                let extent = Vector3::from_element(*radius);
+                Aabb::from_points(center - extent, center + extent)
+            }
+            Distance::Box {
+                center, half_size, ..
+            } => Aabb::from_points(center - half_size, center + half_size),
+            Distance::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => {
+                let outer = major_radius + minor_radius;
+                let extent = Vector3::new(outer, *minor_radius, outer);
+                Aabb::from_points(center - extent, center + extent)
+            }
+            Distance::SmoothUnion { a, b, smoothness } => {
+                // Blending pulls the surface out by at most a quarter of the smoothness.
+                let bbox = Aabb::from_boxes(&a.bounds(), &b.bounds());
+                let padding = smoothness.max(0.0) / 2.0;
+                Aabb::new(
+                    bbox.x.expand(padding),
+                    bbox.y.expand(padding),
+                    bbox.z.expand(padding),
+                )
+            }
+            Distance::SmoothSubtract { a, .. } => a.bounds(),
+            Distance::Repeat {
+                shape,
+                period,
+                count,
+            } => {
+                let bbox = shape.bounds();
+                let extent = period.component_mul(count).abs();
+                Aabb::from_boxes(&(bbox.clone() + -extent), &(bbox + extent))
+            }
+            Distance::Twist { shape, .. } => {
+                let bbox = shape.bounds();
+                let radius = bbox
+                    .vertices()
+                    .map(|vertex| vertex.x.hypot(vertex.z))
+                    .fold(0.0, f64::max);
+                Aabb::new(
+                    Interval::new(-radius, radius),
+                    bbox.y,
+                    Interval::new(-radius, radius),
+                )
+            }
+        }
+    }
+
+    /// How much faster than the distance to the surface the function can change, which shrinks
+    /// each step so twisted shapes are not stepped through.
+    pub fn lipschitz(&self) -> f64 {
+        match self {
+            Distance::Sphere { .. } | Distance::Box { .. } | Distance::Torus { .. } => 1.0,
+            Distance::SmoothUnion { a, b, .. } | Distance::SmoothSubtract { a, b, .. } => {
+                a.lipschitz().max(b.lipschitz())
+            }
+            Distance::Repeat { shape, .. } => shape.lipschitz(),
+            Distance::Twist { shape, rate } => {
+                let bbox = shape.bounds();
+                let radius = bbox
+                    .vertices()
+                    .map(|vertex| vertex.x.hypot(vertex.z))
+                    .fold(0.0, f64::max);
+                shape.lipschitz() * (1.0 + (rate * radius).powi(2)).sqrt()
+            }
+        }
+    }
+}
+
+/// A shape described by a signed distance function, found by sphere tracing: stepping along the
+/// ray by the distance to the nearest surface until reaching it.
+#[derive(Debug, Clone)]
+pub struct Sdf {
+    distance: Distance,
+    lipschitz: f64,
+    material: Material,
+    bbox: Aabb,
+}
+
+impl Sdf {
+    pub fn new(distance: Distance, material: Material) -> Self {
+        let lipschitz = distance.lipschitz();
+        let bbox = distance.bounds();
+        Sdf {
+            distance,
+            lipschitz,
+            material,
+            bbox,
+        }
+    }
+
+    pub fn geometry(distance: Distance, material: Material) -> Geometry {
+        Geometry::Sdf(Sdf::new(distance, material))
+    }
+
+    fn normal(&self, p: &Vector3<f64>) -> Vector3<f64> {
+        // Samples the distance at the corners of a tetrahedron around the point.
+        [
+            Vector3::new(1.0, -1.0, -1.0),
+            Vector3::new(-1.0, -1.0, 1.0),
+            Vector3::new(-1.0, 1.0, -1.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        ]
+        .iter()
+        .map(|k| k * self.distance.distance(&(p + k * GRADIENT_DELTA)))
+        .sum::<Vector3<f64>>()
+        .try_normalize(0.0)
+        .unwrap_or(Vector3::y())
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, r: &Ray, interval: &Interval, record: &mut HitRecord, _: &mut ThreadRng) -> bool {
+        let Some(bounds) = self.bbox.clip(r, interval) else {
+            return false;
+        };
+
+        let speed = r.direction.norm();
+        let mut t = bounds.min;
+
+        // Rays starting inside the shape march towards the surface from within.
+        let side = self.distance.distance(&r.at(t)).signum();
+        for _ in 0..MAX_STEPS {
+            let distance = side * self.distance.distance(&r.at(t)) / self.lipschitz;
+            if distance < EPSILON && t > interval.min {
+                let point = r.at(t);
+
+                record.t = t;
+                record.point = point;
+                record.set_face_normal(r, &self.normal(&point));
+                record.material = self.material.clone();
+                (record.u, record.v) = (0.0, 0.0);
+                record.tangent = Vector3::default();
+                record.bitangent = Vector3::default();
+                return true;
+            }
+
+            t += distance.max(EPSILON) / speed;
+            if t > bounds.max {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+
+    fn sdf(distance: Distance) -> Sdf {
+        Sdf::new(
+            distance,
+            Lambertian::material(SolidColor::texture(Vector3::from_element(0.5))),
+        )
+    }
+
+    fn hit(sdf: &Sdf, origin: [f64; 3], direction: [f64; 3]) -> Option<HitRecord> {
+        let ray = Ray::new(Vector3::from(origin), Vector3::from(direction), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut record = HitRecord::default();
+        sdf.hit(&ray, &interval, &mut record, &mut rand::rng())
+            .then_some(record)
+    }
+
+    fn sphere(center: [f64; 3], radius: f64) -> Distance {
+        Distance::Sphere {
+            center: Vector3::from(center),
+            radius,
+        }
+    }
+
+    /// Whether every point of a grid across the box around the origin reaching `extent` that lies
+    /// inside the shape is within its bounds.
+    fn bounds_hold(distance: &Distance, extent: f64) -> bool {
+        let bbox = distance.bounds();
+        let steps = 40;
+        let coordinate = |i: i32| extent * (2.0 * i as f64 / steps as f64 - 1.0);
+        (0..=steps).all(|i| {
+            (0..=steps).all(|j| {
+                (0..=steps).all(|k| {
+                    let p = Vector3::new(coordinate(i), coordinate(j), coordinate(k));
+                    distance.distance(&p) > 0.0
+                        || (bbox.x.contains(p.x) && bbox.y.contains(p.y) && bbox.z.contains(p.z))
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn test_sphere() {
+        let sphere = sdf(sphere([0.0, 0.0, 0.0], 1.0));
+
+        let record = hit(&sphere, [-5.0, 0.0, 0.0], [1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - 4.0).abs() < 1e-4);
+        assert!(record.front_face);
+        assert!((record.normal - -Vector3::x()).norm() < 1e-4);
+
+        // Steps are measured in units of the ray's direction.
+        let record = hit(&sphere, [0.0, 0.0, -5.0], [0.0, 0.0, 2.0]).unwrap();
+        assert!((record.t - 2.0).abs() < 1e-4);
+
+        let record = hit(&sphere, [-5.0, 0.6, 0.0], [1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - (5.0 - 0.8)).abs() < 1e-4);
+
+        assert!(hit(&sphere, [-5.0, 1.5, 0.0], [1.0, 0.0, 0.0]).is_none());
+        assert!(hit(&sphere, [-5.0, 0.0, 0.0], [-1.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_inside() {
+        let sphere = sdf(sphere([0.0, 0.0, 0.0], 1.0));
+
+        let record = hit(&sphere, [0.0, 0.0, 0.0], [1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-4);
+        assert!(!record.front_face);
+        assert!((record.normal - -Vector3::x()).norm() < 1e-4);
+    }
+
+    #[test]
+    fn test_torus() {
+        let torus = sdf(Distance::Torus {
+            center: Vector3::zeros(),
+            major_radius: 1.0,
+            minor_radius: 0.25,
+        });
+
+        let record = hit(&torus, [-5.0, 0.0, 0.0], [1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - 3.75).abs() < 1e-4);
+        assert!((record.normal - -Vector3::x()).norm() < 1e-4);
+
+        let record = hit(&torus, [0.0, 5.0, 1.0], [0.0, -1.0, 0.0]).unwrap();
+        assert!((record.t - 4.75).abs() < 1e-4);
+        assert!((record.normal - Vector3::y()).norm() < 1e-4);
+
+        // Through the hole.
+        assert!(hit(&torus, [0.0, 5.0, 0.0], [0.0, -1.0, 0.0]).is_none());
+        assert!(hit(&torus, [-5.0, 0.5, 0.0], [1.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_twist() {
+        let rate = 0.5;
+        let twist = Distance::Twist {
+            shape: Box::new(Distance::Box {
+                center: Vector3::zeros(),
+                half_size: Vector3::from_element(1.0),
+                radius: 0.0,
+            }),
+            rate,
+        };
+        // The corners of the box are sqrt(2) away from the axis.
+        let expected = (1.0 + (rate * 2.0f64.sqrt()).powi(2)).sqrt();
+        assert!((twist.lipschitz() - expected).abs() < 1e-12);
+
+        // At height y, the box is turned by rate * y, moving its side out to 1 / cos(rate * y).
+        let twisted = sdf(twist);
+        let record = hit(&twisted, [-5.0, 0.5, 0.0], [1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - (5.0 - 1.0 / (rate * 0.5).cos())).abs() < 1e-4);
+        assert!(bounds_hold(&twisted.distance, 2.0));
+    }
+
+    #[test]
+    fn test_repeat_bounds() {
+        let repeat = Distance::Repeat {
+            shape: Box::new(sphere([0.0, 0.0, 0.0], 0.5)),
+            period: Vector3::new(2.0, 2.0, 2.0),
+            count: Vector3::new(1.0, 0.0, 0.0),
+        };
+        let bbox = repeat.bounds();
+        assert_eq!((bbox.x.min, bbox.x.max), (-2.5, 2.5));
+        assert_eq!((bbox.y.min, bbox.y.max), (-0.5, 0.5));
+        assert!(bounds_hold(&repeat, 4.0));
+
+        let repeat = sdf(repeat);
+        let record = hit(&repeat, [2.0, 5.0, 0.0], [0.0, -1.0, 0.0]).unwrap();
+        assert!((record.t - 4.5).abs() < 1e-4);
+        assert!(hit(&repeat, [4.0, 5.0, 0.0], [0.0, -1.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_smooth_union_bounds() {
+        let smoothness = 1.6;
+        let union = Distance::SmoothUnion {
+            a: Box::new(sphere([-1.0, 0.0, 0.0], 0.75)),
+            b: Box::new(sphere([1.0, 0.0, 0.0], 0.75)),
+            smoothness,
+        };
+        // Blending pulls the surface out by at most a quarter of the smoothness.
+        let bbox = union.bounds();
+        assert!((bbox.x.min - -2.15).abs() < 1e-12 && (bbox.x.max - 2.15).abs() < 1e-12);
+        assert!((bbox.y.min - -1.15).abs() < 1e-12 && (bbox.y.max - 1.15).abs() < 1e-12);
+        assert!(bounds_hold(&union, 3.0));
+
+        // Halfway between the spheres the blend is strongest, bridging the gap between them up
+        // to where the distance to either sphere's center is 0.75 + 0.4.
+        let union = sdf(union);
+        let record = hit(&union, [0.0, 5.0, 0.0], [0.0, -1.0, 0.0]).unwrap();
+        let bridge = (1.15f64.powi(2) - 1.0).sqrt();
+        assert!((record.t - (5.0 - bridge)).abs() < 1e-4);
+    }
+}
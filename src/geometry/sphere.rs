@@ -24,6 +24,22 @@ pub fn get_sphere_uv(point: Vector3<f64>) -> (f64, f64) {
     (phi / (2.0 * PI), theta / PI)
 }
 
+/// The directions in which the `u` and `v` of [`get_sphere_uv`] increase, which vanish at the
+/// poles.
+pub fn get_sphere_tangents(point: Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    let tangent = Vector3::new(point.z, 0.0, -point.x);
+    let bitangent = Vector3::new(
+        point.x * point.y,
+        -(point.x * point.x + point.z * point.z),
+        point.y * point.z,
+    );
+
+    (
+        tangent.try_normalize(f64::EPSILON).unwrap_or_default(),
+        bitangent.try_normalize(f64::EPSILON).unwrap_or_default(),
+    )
+}
+
 impl Sphere {
     pub fn new(
         center: Vector3<f64>,
@@ -85,6 +101,7 @@ impl Hittable for Sphere {
         record.set_face_normal(r, &outward_normal);
         record.material = self.material.clone();
         (record.u, record.v) = get_sphere_uv(outward_normal);
+        (record.tangent, record.bitangent) = get_sphere_tangents(outward_normal);
 
         true
     }
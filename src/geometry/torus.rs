@@ -0,0 +1,187 @@
+use crate::geometry::Geometry;
+use crate::geometry::HitRecord;
+use crate::geometry::Hittable;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::frame::Frame;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::math::solve_quartic;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
+
+/// A torus centered on `center`, sweeping a tube of `minor_radius` around `axis` at a distance
+/// of `major_radius`.
+#[derive(Debug, Clone)]
+pub struct Torus {
+    frame: Frame,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Material,
+    bbox: Aabb,
+}
+
+impl Torus {
+    pub fn new(
+        center: Vector3<f64>,
+        axis: Vector3<f64>,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Material,
+    ) -> Self {
+        let frame = Frame::new(center, axis);
+        let extent = frame.disk_extent(major_radius + minor_radius) + frame.w.abs() * minor_radius;
+        let bbox = Aabb::from_points(center - extent, center + extent);
+
+        Torus {
+            frame,
+            major_radius,
+            minor_radius,
+            material,
+            bbox,
+        }
+    }
+
+    pub fn geometry(
+        center: Vector3<f64>,
+        axis: Vector3<f64>,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Material,
+    ) -> Geometry {
+        Geometry::Torus(Torus::new(
+            center,
+            axis,
+            major_radius,
+            minor_radius,
+            material,
+        ))
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, interval: &Interval, record: &mut HitRecord, _: &mut ThreadRng) -> bool {
+        let local = self.frame.ray_to_local(r);
+        let length = local.direction.norm();
+        let d = local.direction / length;
+
+        // Solving from the point of the ray closest to the center keeps the quartic's
+        // coefficients small for distant rays.
+        let start = -local.origin.dot(&d);
+        let o = local.origin + d * start;
+
+        // The torus is where (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2).
+        let major2 = self.major_radius * self.major_radius;
+        let e = o.norm_squared() + major2 - self.minor_radius * self.minor_radius;
+        let f = o.dot(&d);
+        let roots = solve_quartic(
+            4.0 * f,
+            4.0 * f * f + 2.0 * e - 4.0 * major2 * (d.x * d.x + d.y * d.y),
+            4.0 * e * f - 8.0 * major2 * (o.x * d.x + o.y * d.y),
+            e * e - 4.0 * major2 * (o.x * o.x + o.y * o.y),
+        );
+
+        let Some(t) = roots
+            .into_iter()
+            .map(|root| (root + start) / length)
+            .find(|t| interval.surrounds(*t))
+        else {
+            return false;
+        };
+
+        let p = local.at(t);
+        let distance = p.x.hypot(p.y);
+        let around = p.y.atan2(p.x);
+        let across = p.z.atan2(distance - self.major_radius);
+
+        let tube_center = Vector3::new(around.cos(), around.sin(), 0.0) * self.major_radius;
+        let normal = (p - tube_center).normalize();
+        let tangent = Vector3::new(-around.sin(), around.cos(), 0.0);
+        let bitangent = Vector3::new(
+            -across.sin() * around.cos(),
+            -across.sin() * around.sin(),
+            across.cos(),
+        );
+
+        record.t = t;
+        record.point = r.at(t);
+        record.set_face_normal(r, &self.frame.vector_to_world(&normal));
+        record.material = self.material.clone();
+        record.u = (around / (2.0 * PI)).rem_euclid(1.0);
+        record.v = (across / (2.0 * PI)).rem_euclid(1.0);
+        record.tangent = self.frame.vector_to_world(&tangent);
+        record.bitangent = self.frame.vector_to_world(&bitangent);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+
+    fn material() -> Material {
+        Lambertian::material(SolidColor::texture(Vector3::from_element(0.5)))
+    }
+
+    fn hit(torus: &Torus, origin: [f64; 3], direction: [f64; 3]) -> Option<HitRecord> {
+        let ray = Ray::new(Vector3::from(origin), Vector3::from(direction), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut record = HitRecord::default();
+        torus
+            .hit(&ray, &interval, &mut record, &mut rand::rng())
+            .then_some(record)
+    }
+
+    fn torus() -> Torus {
+        Torus::new(Vector3::zeros(), Vector3::z(), 1.0, 0.25, material())
+    }
+
+    #[test]
+    fn test_torus_nearest_root() {
+        // The ray crosses the tube four times, and the hit is the first of them.
+        let record = hit(&torus(), [5.0, 0.0, 0.0], [-1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - 3.75).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.normal - Vector3::x()).norm() < 1e-9);
+
+        // Starting inside the tube, the ray leaves through its outer wall.
+        let record = hit(&torus(), [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - 0.25).abs() < 1e-9);
+        assert!(!record.front_face);
+
+        // Starting past the first wall, the ray meets the far side of the tube.
+        let record = hit(&torus(), [0.5, 0.0, 0.0], [-1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - 1.25).abs() < 1e-9);
+        assert!(record.front_face);
+
+        // Far away rays solve from their closest point to the center, keeping this precise.
+        let record = hit(&torus(), [1e4, 0.0, 0.0], [-1.0, 0.0, 0.0]).unwrap();
+        assert!((record.t - (1e4 - 1.25)).abs() < 1e-6);
+
+        assert!(hit(&torus(), [0.0, 0.0, 5.0], [0.0, 0.0, -1.0]).is_none());
+        assert!(hit(&torus(), [5.0, 0.0, 0.5], [-1.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_torus_uv() {
+        let record = hit(&torus(), [5.0, 0.0, 0.0], [-1.0, 0.0, 0.0]).unwrap();
+        assert!((record.u - 0.0).abs() < 1e-9 && (record.v - 0.0).abs() < 1e-9);
+
+        let record = hit(&torus(), [0.0, 1.0, 5.0], [0.0, 0.0, -1.0]).unwrap();
+        assert!((record.t - 4.75).abs() < 1e-9);
+        assert!((record.u - 0.25).abs() < 1e-9 && (record.v - 0.25).abs() < 1e-9);
+        assert!((record.tangent - -Vector3::x()).norm() < 1e-9);
+        assert!((record.bitangent - -Vector3::y()).norm() < 1e-9);
+
+        let record = hit(&torus(), [0.0, -0.5, 0.0], [0.0, 1.0, 0.0]).unwrap();
+        assert!((record.u - 0.25).abs() < 1e-9 && (record.v - 0.5).abs() < 1e-9);
+    }
+}
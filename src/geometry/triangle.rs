@@ -2,9 +2,11 @@ use crate::geometry::Geometry;
 use crate::geometry::HitRecord;
 use crate::geometry::Hittable;
 use crate::geometry::aabb::Aabb;
+use crate::geometry::opacity::Opacity;
 use crate::interval::Interval;
 use crate::material::Material;
 use crate::ray::Ray;
+use nalgebra::Vector2;
 use nalgebra::Vector3;
 use rand::rngs::ThreadRng;
 
@@ -12,17 +14,20 @@ use rand::rngs::ThreadRng;
 pub struct Vertex {
     pub position: Vector3<f64>,
     pub normal: Option<Vector3<f64>>,
+    pub uv: Option<Vector2<f64>>,
 }
 
 impl Vertex {
-    pub fn new(position: Vector3<f64>, normal: Option<Vector3<f64>>) -> Self {
-        Vertex { position, normal }
-    }
-
-    pub fn from_poligon(position: (f32, f32, f32, f32), normal: Option<(f32, f32, f32)>) -> Self {
-        let normal = normal.map(|n| Vector3::new(n.0 as f64, n.1 as f64, n.2 as f64));
-        let position = Vector3::new(position.0 as f64, position.1 as f64, position.2 as f64);
-        Vertex { position, normal }
+    pub fn new(
+        position: Vector3<f64>,
+        normal: Option<Vector3<f64>>,
+        uv: Option<Vector2<f64>>,
+    ) -> Self {
+        Vertex {
+            position,
+            normal,
+            uv,
+        }
     }
 }
 
@@ -33,10 +38,17 @@ pub struct Triangle {
     pub c: Vertex,
     pub bbox: Aabb,
     pub material: Material,
+    pub opacity: Option<Opacity>,
 }
 
 impl Triangle {
-    pub fn new(a: Vertex, b: Vertex, c: Vertex, material: Material) -> Triangle {
+    pub fn new(
+        a: Vertex,
+        b: Vertex,
+        c: Vertex,
+        material: Material,
+        opacity: Option<Opacity>,
+    ) -> Triangle {
         let a_to_b = Aabb::from_points(a.position, b.position);
         let b_to_c = Aabb::from_points(b.position, c.position);
         let c_to_a = Aabb::from_points(c.position, a.position);
@@ -47,41 +59,113 @@ impl Triangle {
             c,
             material,
             bbox,
+            opacity,
         }
     }
 
-    pub fn geometry(a: Vertex, b: Vertex, c: Vertex, material: Material) -> Geometry {
-        Geometry::Triangle(Triangle::new(a, b, c, material))
+    pub fn geometry(
+        a: Vertex,
+        b: Vertex,
+        c: Vertex,
+        material: Material,
+        opacity: Option<Opacity>,
+    ) -> Geometry {
+        Geometry::Triangle(Triangle::new(a, b, c, material, opacity))
     }
 }
 
-impl Hittable for Triangle {
-    fn hit(&self, r: &Ray, interval: &Interval, record: &mut HitRecord, _: &mut ThreadRng) -> bool {
-        let e1 = self.b.position - self.a.position;
-        let e2 = self.c.position - self.a.position;
-
-        let ray_cross_e2 = r.direction.cross(&e2);
-        let determinant = e1.dot(&ray_cross_e2);
-        if determinant.abs() < f64::EPSILON {
-            return false; // Ray is parallel to the triangle
-        }
+/// Intersects the ray with the triangle `a`, `b`, `c` using the Möller–Trumbore algorithm,
+/// returning the ray's `t` and the barycentric weights of `b` and `c` at the hit.
+pub fn intersect(
+    r: &Ray,
+    a: &Vector3<f64>,
+    b: &Vector3<f64>,
+    c: &Vector3<f64>,
+) -> Option<(f64, f64, f64)> {
+    let e1 = b - a;
+    let e2 = c - a;
+
+    let ray_cross_e2 = r.direction.cross(&e2);
+    let determinant = e1.dot(&ray_cross_e2);
+    if determinant.abs() < f64::EPSILON {
+        return None; // Ray is parallel to the triangle
+    }
 
-        let inv_determinant = 1.0 / determinant;
-        let s = r.origin - self.a.position;
+    let inv_determinant = 1.0 / determinant;
+    let s = r.origin - a;
 
-        let u = inv_determinant * s.dot(&ray_cross_e2);
-        if !(0.0..=1.0).contains(&u) {
-            return false; // Intersection is outside the triangle
-        }
+    let u = inv_determinant * s.dot(&ray_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None; // Intersection is outside the triangle
+    }
+
+    let t_cross_e1 = s.cross(&e1);
+    let v = inv_determinant * r.direction.dot(&t_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None; // Intersection is outside the triangle
+    }
+
+    let t = inv_determinant * e2.dot(&t_cross_e1);
+    if t <= f64::EPSILON {
+        return None;
+    }
+
+    Some((t, u, v))
+}
+
+/// The directions in which the texture coordinates `uvs` increase across the triangle `points`,
+/// or `None` when the coordinates do not span the triangle.
+pub fn uv_tangents(
+    points: [Vector3<f64>; 3],
+    uvs: [Vector2<f64>; 3],
+) -> Option<(Vector3<f64>, Vector3<f64>)> {
+    let e1 = points[1] - points[0];
+    let e2 = points[2] - points[0];
+    let duv1 = uvs[1] - uvs[0];
+    let duv2 = uvs[2] - uvs[0];
+
+    let determinant = duv1.x * duv2.y - duv2.x * duv1.y;
+    if determinant.abs() < f64::EPSILON {
+        return None;
+    }
 
-        let t_cross_e1 = s.cross(&e1);
-        let v = inv_determinant * r.direction.dot(&t_cross_e1);
-        if v < 0.0 || u + v > 1.0 {
-            return false; // Intersection is outside the triangle
+    let tangent = (e1 * duv2.y - e2 * duv1.y) / determinant;
+    let bitangent = (e2 * duv1.x - e1 * duv2.x) / determinant;
+    Some((tangent, bitangent))
+}
+
+impl Hittable for Triangle {
+    fn hit(
+        &self,
+        r: &Ray,
+        interval: &Interval,
+        record: &mut HitRecord,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        let Some((t, u, v)) = intersect(r, &self.a.position, &self.b.position, &self.c.position)
+        else {
+            return false;
+        };
+        if !interval.contains(t) {
+            return false;
         }
 
-        let t = inv_determinant * e2.dot(&t_cross_e1);
-        if t <= f64::EPSILON || !interval.contains(t) {
+        let points = [self.a.position, self.b.position, self.c.position];
+        let (uv, (tangent, bitangent)) = match (self.a.uv, self.b.uv, self.c.uv) {
+            (Some(a_uv), Some(b_uv), Some(c_uv)) => (
+                (1.0 - u - v) * a_uv + u * b_uv + v * c_uv,
+                uv_tangents(points, [a_uv, b_uv, c_uv]).unwrap_or_default(),
+            ),
+            _ => (
+                Vector2::new(u, v),
+                (points[1] - points[0], points[2] - points[0]),
+            ),
+        };
+
+        let point = r.at(t);
+        if let Some(opacity) = &self.opacity
+            && !opacity.is_opaque(uv.x, uv.y, point, rng)
+        {
             return false;
         }
 
@@ -89,15 +173,18 @@ impl Hittable for Triangle {
             (Some(a_normal), Some(b_normal), Some(c_normal)) => {
                 ((1.0 - u - v) * a_normal + u * b_normal + v * c_normal).normalize()
             }
-            _ => e1.cross(&e2).normalize(),
+            _ => (self.b.position - self.a.position)
+                .cross(&(self.c.position - self.a.position))
+                .normalize(),
         };
 
         record.t = t;
-        record.point = r.at(t);
+        record.point = point;
         record.set_face_normal(r, &outward_normal);
         record.material = self.material.clone();
-        record.u = u;
-        record.v = v;
+        (record.u, record.v) = (uv.x, uv.y);
+        record.tangent = tangent.try_normalize(0.0).unwrap_or_default();
+        record.bitangent = bitangent.try_normalize(0.0).unwrap_or_default();
 
         true
     }
@@ -106,3 +193,33 @@ impl Hittable for Triangle {
         self.bbox.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::texture::SolidColor;
+
+    fn masked(opacity: f64) -> Triangle {
+        let mask = SolidColor::texture(Vector3::from_element(opacity));
+        Triangle::new(
+            Vertex::new(Vector3::new(-1.0, -1.0, 0.0), None, None),
+            Vertex::new(Vector3::new(1.0, -1.0, 0.0), None, None),
+            Vertex::new(Vector3::new(0.0, 1.0, 0.0), None, None),
+            Lambertian::material(SolidColor::texture(Vector3::from_element(0.5))),
+            Some(Opacity::new(mask, Some(0.5))),
+        )
+    }
+
+    fn hits(triangle: &Triangle) -> bool {
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 1.0), -Vector3::z(), 0.0);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        triangle.hit(&ray, &interval, &mut HitRecord::default(), &mut rand::rng())
+    }
+
+    #[test]
+    fn test_threshold_mask() {
+        assert!(!hits(&masked(0.3)));
+        assert!(hits(&masked(0.7)));
+    }
+}
@@ -4,33 +4,164 @@ use crate::geometry::Hittable;
 use crate::geometry::aabb::Aabb;
 use crate::interval::Interval;
 use crate::material::Material;
-use crate::material::isotropic::Isotropic;
-use crate::material::texture::Texture;
+use crate::material::texture::Noise;
+use crate::material::texture::Sample;
 use crate::ray::Ray;
 use nalgebra::Vector3;
 use rand::prelude::*;
 use rand::rngs::ThreadRng;
+use std::error::Error;
+use std::io::Read;
+
+/// How the density of a volume varies across it, as a fraction of its `density`.
+#[derive(Debug, Clone)]
+pub enum Density {
+    Constant,
+    /// Scaled by the brightness of the noise.
+    Noise(Noise),
+    /// A voxel grid stretched over the bounding box of the volume.
+    Grid(Grid),
+}
+
+impl Density {
+    fn sample(&self, p: &Vector3<f64>, bbox: &Aabb) -> f64 {
+        match self {
+            Density::Constant => 1.0,
+            Density::Noise(noise) => noise.sample(0.0, 0.0, *p).mean().min(1.0),
+            Density::Grid(grid) => {
+                let local = Vector3::new(
+                    (p.x - bbox.x.min) / bbox.x.size(),
+                    (p.y - bbox.y.min) / bbox.y.size(),
+                    (p.z - bbox.z.min) / bbox.z.size(),
+                );
+                grid.sample(&local)
+            }
+        }
+    }
+
+    /// The highest fraction the density reaches anywhere.
+    fn max(&self) -> f64 {
+        match self {
+            Density::Constant | Density::Noise(_) => 1.0,
+            Density::Grid(grid) => grid.max,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Grid {
+    resolution: [usize; 3],
+    values: Vec<f64>,
+    max: f64,
+}
+
+fn read_i32(reader: &mut impl Read) -> Result<i32, Box<dyn Error>> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+impl Grid {
+    /// Reads a grid in Mitsuba's `.vol` format, keeping the first channel of each voxel.
+    pub fn read(reader: &mut impl Read) -> Result<Grid, Box<dyn Error>> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != *b"VOL\x03" {
+            return Err("Volume grids must be version 3 .vol files".into());
+        }
+        if read_i32(reader)? != 1 {
+            return Err("Volume grids must hold 32-bit floats".into());
+        }
+
+        let resolution = [read_i32(reader)?, read_i32(reader)?, read_i32(reader)?];
+        let channels = read_i32(reader)?;
+        if resolution.iter().any(|n| *n <= 0) || channels <= 0 {
+            return Err("Volume grid has no voxels".into());
+        }
+        let resolution = resolution.map(|n| n as usize);
+
+        // The bounding box stored in the file is ignored, the grid always fills the volume.
+        reader.read_exact(&mut [0; 24])?;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let voxels = resolution.iter().product::<usize>();
+        let stride = channels as usize * 4;
+        if data.len() < voxels * stride {
+            return Err("Volume grid is missing voxels".into());
+        }
+
+        let values: Vec<f64> = data
+            .chunks_exact(stride)
+            .take(voxels)
+            .map(|voxel| f32::from_le_bytes([voxel[0], voxel[1], voxel[2], voxel[3]]) as f64)
+            .map(|value| value.max(0.0))
+            .collect();
+        let max = values.iter().copied().fold(0.0, f64::max);
+
+        Ok(Grid {
+            resolution,
+            values,
+            max,
+        })
+    }
+
+    fn value(&self, x: usize, y: usize, z: usize) -> f64 {
+        let [nx, ny, _] = self.resolution;
+        self.values[(z * ny + y) * nx + x]
+    }
+
+    /// Trilinearly interpolates between voxel centers, with `local` spanning 0 to 1 on each axis.
+    fn sample(&self, local: &Vector3<f64>) -> f64 {
+        let mut cells = [(0, 0, 0.0); 3];
+        for (axis, cell) in cells.iter_mut().enumerate() {
+            let size = self.resolution[axis];
+            let position = (local[axis] * size as f64 - 0.5).clamp(0.0, (size - 1) as f64);
+            let low = position.floor() as usize;
+            *cell = (low, (low + 1).min(size - 1), position - low as f64);
+        }
+        let [(x0, x1, fx), (y0, y1, fy), (z0, z1, fz)] = cells;
+
+        let lerp = |a: f64, b: f64, f: f64| a + (b - a) * f;
+        let plane = |z| {
+            lerp(
+                lerp(self.value(x0, y0, z), self.value(x1, y0, z), fx),
+                lerp(self.value(x0, y1, z), self.value(x1, y1, z), fx),
+                fy,
+            )
+        };
+        lerp(plane(z0), plane(z1), fz)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Volume {
     boundry: Box<Geometry>,
-    neg_inv_density: f64,
+    density: f64,
+    field: Density,
     phase_function: Material,
+    bbox: Aabb,
 }
 
 impl Volume {
-    pub fn new(boundry: Geometry, density: f64, texture: Texture) -> Self {
+    pub fn new(boundry: Geometry, density: f64, field: Density, phase_function: Material) -> Self {
+        let bbox = boundry.bounding_box();
         let boundry = Box::new(boundry);
-        let neg_inv_density = -1.0 / density;
-        let phase_function = Isotropic::material(texture);
         Volume {
             boundry,
-            neg_inv_density,
+            density,
+            field,
             phase_function,
+            bbox,
         }
     }
-    pub fn geometry(boundry: Geometry, density: f64, texture: Texture) -> Geometry {
-        Geometry::Volume(Volume::new(boundry, density, texture))
+    pub fn geometry(
+        boundry: Geometry,
+        density: f64,
+        field: Density,
+        phase_function: Material,
+    ) -> Geometry {
+        Geometry::Volume(Volume::new(boundry, density, field, phase_function))
     }
 }
 
@@ -79,14 +210,31 @@ impl Hittable for Volume {
 
         let ray_length = r.direction.norm();
         let distance_inside_boundary = (record_b.t - record_a.t) * ray_length;
-        let hit_distance = self.neg_inv_density * rng.random::<f64>().ln();
 
-        if hit_distance > distance_inside_boundary {
+        // Delta tracking: collisions are sampled against the highest density in the volume, and
+        // each one is kept in proportion to the actual density there, otherwise the ray carries
+        // on as if nothing was hit.
+        let majorant = self.density * self.field.max();
+        if majorant <= 0.0 {
             return false;
         }
 
-        record.t = record_a.t + hit_distance / ray_length;
-        record.point = r.at(record.t);
+        let mut hit_distance = 0.0;
+        loop {
+            hit_distance -= (1.0 - rng.random::<f64>()).ln() / majorant;
+            if hit_distance > distance_inside_boundary {
+                return false;
+            }
+
+            let t = record_a.t + hit_distance / ray_length;
+            let point = r.at(t);
+            let density = self.density * self.field.sample(&point, &self.bbox);
+            if rng.random::<f64>() * majorant < density {
+                record.t = t;
+                record.point = point;
+                break;
+            }
+        }
 
         record.normal = Vector3::new(1.0, 0.0, 0.0);
         record.front_face = true;
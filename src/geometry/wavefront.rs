@@ -1,117 +1,98 @@
-use crate::geometry::BvhNode;
-use crate::geometry::Geometry;
-use crate::geometry::HitRecord;
-use crate::geometry::Hittable;
-use crate::geometry::aabb::Aabb;
-use crate::geometry::triangle::Triangle;
-use crate::geometry::triangle::Vertex;
-use crate::interval::Interval;
+use crate::geometry::mesh::Face;
+use crate::geometry::mesh::MeshData;
 use crate::material::Material;
-use crate::ray::Ray;
+use nalgebra::Vector2;
+use nalgebra::Vector3;
 use obj::raw::object::Group;
 use obj::raw::object::Polygon;
 use obj::raw::object::RawObj;
-use rand::rngs::ThreadRng;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
-pub struct Wavefront {
-    children: Box<BvhNode>,
+/// The position, texture coordinate and normal indices of every corner of `polygon`.
+fn corners(polygon: &Polygon) -> Vec<(usize, Option<usize>, Option<usize>)> {
+    match polygon {
+        Polygon::P(polygon) => polygon.iter().map(|&p| (p, None, None)).collect(),
+        Polygon::PT(polygon) => polygon.iter().map(|&(p, t)| (p, Some(t), None)).collect(),
+        Polygon::PN(polygon) => polygon.iter().map(|&(p, n)| (p, None, Some(n))).collect(),
+        Polygon::PTN(polygon) => polygon
+            .iter()
+            .map(|&(p, t, n)| (p, Some(t), Some(n)))
+            .collect(),
+    }
 }
 
-impl Wavefront {
-    pub fn new(object: &RawObj, group: Option<&Group>, material: Material) -> Self {
-        let polygons = match group {
-            Some(group) => group
-                .polygons
-                .iter()
-                .flat_map(|index_range| {
-                    (index_range.start..index_range.end).map(|index| object.polygons[index].clone())
-                })
-                .collect(),
-            None => object.polygons.clone(),
-        };
-
-        let primitives: Vec<Geometry> = polygons
+/// Builds the mesh of `object` (or of a single `group` within it). Every face is given the entry
+/// of `materials` named by its `usemtl` statement, where faces preceding any `usemtl` statement
+/// belong to the empty name. Only the names of the faces built need an entry.
+pub fn mesh_data(
+    object: &RawObj,
+    group: Option<&Group>,
+    materials: &HashMap<String, Material>,
+) -> MeshData {
+    let mut data = MeshData {
+        positions: object
+            .positions
             .iter()
-            .flat_map(|polygon| match polygon {
-                Polygon::P(polygon) => (1..polygon.len() - 1)
-                    .map(|i| {
-                        let a = Vertex::from_poligon(object.positions[polygon[0]], None);
-                        let b = Vertex::from_poligon(object.positions[polygon[i]], None);
-                        let c = Vertex::from_poligon(object.positions[polygon[i + 1]], None);
-
-                        Triangle::geometry(a, b, c, material.clone())
-                    })
-                    .collect::<Vec<Geometry>>(),
-                Polygon::PT(polygon) => (1..polygon.len() - 1)
-                    .map(|i| {
-                        let a = Vertex::from_poligon(object.positions[polygon[0].0], None);
-                        let b = Vertex::from_poligon(object.positions[polygon[i].0], None);
-                        let c = Vertex::from_poligon(object.positions[polygon[i + 1].0], None);
-
-                        Triangle::geometry(a, b, c, material.clone())
-                    })
-                    .collect::<Vec<Geometry>>(),
-                Polygon::PN(polygon) => (1..polygon.len() - 1)
-                    .map(|i| {
-                        let a = Vertex::from_poligon(
-                            object.positions[polygon[0].0],
-                            Some(object.normals[polygon[0].1]),
-                        );
-                        let b = Vertex::from_poligon(
-                            object.positions[polygon[i].0],
-                            Some(object.normals[polygon[i].1]),
-                        );
-                        let c = Vertex::from_poligon(
-                            object.positions[polygon[i + 1].0],
-                            Some(object.normals[polygon[i + 1].1]),
-                        );
+            .map(|p| Vector3::new(p.0 as f64, p.1 as f64, p.2 as f64))
+            .collect(),
+        normals: object
+            .normals
+            .iter()
+            .map(|n| Vector3::new(n.0 as f64, n.1 as f64, n.2 as f64))
+            .collect(),
+        // Wavefront places v = 0 at the bottom of the image, textures sample from the top.
+        uvs: object
+            .tex_coords
+            .iter()
+            .map(|t| Vector2::new(t.0 as f64, 1.0 - t.1 as f64))
+            .collect(),
+        ..MeshData::default()
+    };
 
-                        Triangle::geometry(a, b, c, material.clone())
-                    })
-                    .collect::<Vec<Geometry>>(),
-                Polygon::PTN(polygon) => (1..polygon.len() - 1)
-                    .map(|i| {
-                        let a = Vertex::from_poligon(
-                            object.positions[polygon[0].0],
-                            Some(object.normals[polygon[0].2]),
-                        );
-                        let b = Vertex::from_poligon(
-                            object.positions[polygon[i].0],
-                            Some(object.normals[polygon[i].2]),
-                        );
-                        let c = Vertex::from_poligon(
-                            object.positions[polygon[i + 1].0],
-                            Some(object.normals[polygon[i + 1].2]),
-                        );
+    let mut polygon_materials: Vec<Option<u32>> = vec![None; object.polygons.len()];
+    for (name, mesh) in object.meshes.iter() {
+        let Some(material) = materials.get(name) else {
+            continue;
+        };
+        let index = data.materials.len() as u32;
+        data.materials.push(material.clone());
+        for range in mesh.polygons.iter() {
+            polygon_materials[range.start..range.end].fill(Some(index));
+        }
+    }
 
-                        Triangle::geometry(a, b, c, material.clone())
-                    })
-                    .collect::<Vec<Geometry>>(),
-            })
-            .collect::<Vec<Geometry>>();
+    let indices: Vec<usize> = match group {
+        Some(group) => group
+            .polygons
+            .iter()
+            .flat_map(|range| range.start..range.end)
+            .collect(),
+        None => (0..object.polygons.len()).collect(),
+    };
 
-        let children = Box::new(BvhNode::new(primitives.to_vec()));
+    for index in indices {
+        let corners = corners(&object.polygons[index]);
+        let material = polygon_materials[index].expect("every face built should have a material");
 
-        Wavefront { children }
-    }
-    pub fn geometry(object: &RawObj, group: Option<&Group>, material: Material) -> Geometry {
-        Geometry::Wavefront(Wavefront::new(object, group, material))
-    }
-}
+        for i in 1..corners.len().saturating_sub(1) {
+            let triangle = [corners[0], corners[i], corners[i + 1]];
+            let normals = triangle.map(|(_, _, n)| n);
+            let uvs = triangle.map(|(_, t, _)| t);
 
-impl Hittable for Wavefront {
-    fn hit(
-        &self,
-        r: &Ray,
-        interval: &Interval,
-        record: &mut HitRecord,
-        rng: &mut ThreadRng,
-    ) -> bool {
-        self.children.hit(r, interval, record, rng)
+            data.faces.push(Face {
+                positions: triangle.map(|(p, _, _)| p as u32),
+                normals: match normals {
+                    [Some(a), Some(b), Some(c)] => Some([a as u32, b as u32, c as u32]),
+                    _ => None,
+                },
+                uvs: match uvs {
+                    [Some(a), Some(b), Some(c)] => Some([a as u32, b as u32, c as u32]),
+                    _ => None,
+                },
+                material,
+            });
+        }
     }
 
-    fn bounding_box(&self) -> Aabb {
-        self.children.bounding_box()
-    }
+    data
 }
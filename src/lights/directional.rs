@@ -0,0 +1,30 @@
+use crate::lights::Illuminate;
+use crate::lights::Incident;
+use crate::lights::LightSource;
+use nalgebra::Vector3;
+
+/// A light so far away, like the sun, that it shines along the same `direction` everywhere.
+#[derive(Debug, Clone)]
+pub struct Directional {
+    pub direction: Vector3<f64>,
+    pub irradiance: Vector3<f64>,
+}
+
+impl Directional {
+    pub fn light(direction: Vector3<f64>, irradiance: Vector3<f64>) -> LightSource {
+        LightSource::Directional(Directional {
+            direction: direction.normalize(),
+            irradiance,
+        })
+    }
+}
+
+impl Illuminate for Directional {
+    fn illuminate(&self, _: &Vector3<f64>) -> Incident {
+        Incident {
+            direction: -self.direction,
+            distance: f64::INFINITY,
+            irradiance: self.irradiance,
+        }
+    }
+}
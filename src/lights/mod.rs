@@ -0,0 +1,54 @@
+pub mod directional;
+pub mod point;
+pub mod spot;
+
+use crate::lights::directional::Directional;
+use crate::lights::point::Point;
+use crate::lights::spot::Spot;
+use nalgebra::Vector3;
+
+/// Light arriving at a point straight from a light source.
+#[derive(Debug, Clone, Copy)]
+pub struct Incident {
+    /// The unit direction from the point towards the light.
+    pub direction: Vector3<f64>,
+    /// How far away the light is, infinite for lights outside of the scene.
+    pub distance: f64,
+    /// The light falling on a surface facing the light.
+    pub irradiance: Vector3<f64>,
+}
+
+impl Incident {
+    /// No light at all, for points where a light cannot shine from anywhere, like its own
+    /// position.
+    pub fn dark() -> Self {
+        Incident {
+            direction: Vector3::z(),
+            distance: 0.0,
+            irradiance: Vector3::default(),
+        }
+    }
+}
+
+pub trait Illuminate {
+    fn illuminate(&self, point: &Vector3<f64>) -> Incident;
+}
+
+/// Lights with no size, which rays can never hit by chance and are only seen by sampling them
+/// directly at every point.
+#[derive(Debug, Clone)]
+pub enum LightSource {
+    Point(Point),
+    Spot(Spot),
+    Directional(Directional),
+}
+
+impl Illuminate for LightSource {
+    fn illuminate(&self, point: &Vector3<f64>) -> Incident {
+        match self {
+            LightSource::Point(light) => light.illuminate(point),
+            LightSource::Spot(light) => light.illuminate(point),
+            LightSource::Directional(light) => light.illuminate(point),
+        }
+    }
+}
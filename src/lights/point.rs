@@ -0,0 +1,61 @@
+use crate::lights::Illuminate;
+use crate::lights::Incident;
+use crate::lights::LightSource;
+use nalgebra::Vector3;
+
+/// A light shining equally in every direction from a `position`, with an `intensity` per unit of
+/// solid angle.
+#[derive(Debug, Clone)]
+pub struct Point {
+    pub position: Vector3<f64>,
+    pub intensity: Vector3<f64>,
+}
+
+impl Point {
+    pub fn light(position: Vector3<f64>, intensity: Vector3<f64>) -> LightSource {
+        LightSource::Point(Point {
+            position,
+            intensity,
+        })
+    }
+}
+
+impl Illuminate for Point {
+    fn illuminate(&self, point: &Vector3<f64>) -> Incident {
+        let to_light = self.position - point;
+        let distance = to_light.norm();
+        if distance == 0.0 {
+            return Incident::dark();
+        }
+        Incident {
+            direction: to_light / distance,
+            distance,
+            irradiance: self.intensity / (distance * distance),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inverse_square() {
+        let light = Point::light(Vector3::new(0.0, 2.0, 0.0), Vector3::from_element(8.0));
+        let incident = light.illuminate(&Vector3::default());
+        assert!((incident.direction - Vector3::y()).norm() < 1e-12);
+        assert!((incident.distance - 2.0).abs() < 1e-12);
+        assert!((incident.irradiance - Vector3::from_element(2.0)).norm() < 1e-12);
+
+        let farther = light.illuminate(&Vector3::new(0.0, -2.0, 0.0));
+        assert!((farther.irradiance - Vector3::from_element(0.5)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_point_on_light_is_dark() {
+        let light = Point::light(Vector3::new(1.0, 2.0, 3.0), Vector3::from_element(8.0));
+        let incident = light.illuminate(&Vector3::new(1.0, 2.0, 3.0));
+        assert!(incident.direction.iter().all(|c| c.is_finite()));
+        assert_eq!(incident.irradiance, Vector3::default());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::lights::Illuminate;
+use crate::lights::Incident;
+use crate::lights::LightSource;
+use nalgebra::Vector3;
+
+/// A point light only shining into a cone around its `direction`, fading out between the cosines
+/// of the `inner` and `outer` angles from it.
+#[derive(Debug, Clone)]
+pub struct Spot {
+    pub position: Vector3<f64>,
+    pub direction: Vector3<f64>,
+    pub intensity: Vector3<f64>,
+    pub cos_inner: f64,
+    pub cos_outer: f64,
+}
+
+impl Spot {
+    pub fn light(
+        position: Vector3<f64>,
+        direction: Vector3<f64>,
+        intensity: Vector3<f64>,
+        inner: f64,
+        outer: f64,
+    ) -> LightSource {
+        LightSource::Spot(Spot {
+            position,
+            direction: direction.normalize(),
+            intensity,
+            cos_inner: inner.to_radians().cos(),
+            cos_outer: outer.to_radians().cos(),
+        })
+    }
+
+    /// How much of the light shines along a direction with a cosine of `cos_theta` to the axis
+    /// of the cone, smoothly falling off towards its edge.
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if self.cos_inner <= self.cos_outer {
+            return if cos_theta >= self.cos_outer {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        let t = ((cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Illuminate for Spot {
+    fn illuminate(&self, point: &Vector3<f64>) -> Incident {
+        let to_light = self.position - point;
+        let distance = to_light.norm();
+        if distance == 0.0 {
+            return Incident::dark();
+        }
+        let direction = to_light / distance;
+        Incident {
+            direction,
+            distance,
+            irradiance: self.intensity * self.falloff(-direction.dot(&self.direction))
+                / (distance * distance),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spot() -> Spot {
+        let LightSource::Spot(spot) = Spot::light(
+            Vector3::default(),
+            -Vector3::y(),
+            Vector3::from_element(1.0),
+            20.0,
+            40.0,
+        ) else {
+            unreachable!()
+        };
+        spot
+    }
+
+    #[test]
+    fn test_falloff() {
+        let spot = spot();
+        assert_eq!(spot.falloff(1.0), 1.0);
+        assert_eq!(spot.falloff(spot.cos_inner), 1.0);
+        assert_eq!(spot.falloff(spot.cos_outer), 0.0);
+        assert_eq!(spot.falloff(0.0), 0.0);
+
+        // Smoothly rising from the outer edge of the cone to the inner one.
+        let steps: Vec<f64> = (0..=10)
+            .map(|i| i as f64 / 10.0)
+            .map(|t| spot.falloff(spot.cos_outer + t * (spot.cos_inner - spot.cos_outer)))
+            .collect();
+        assert!(steps.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!((steps[5] - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_point_on_light_is_dark() {
+        let incident = spot().illuminate(&Vector3::default());
+        assert!(incident.direction.iter().all(|c| c.is_finite()));
+        assert_eq!(incident.irradiance, Vector3::default());
+    }
+}
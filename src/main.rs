@@ -2,10 +2,12 @@ mod camera;
 mod config;
 mod geometry;
 mod interval;
+mod lights;
 mod material;
 mod math;
 mod noise;
 mod ray;
+mod spectrum;
 
 use camera::Camera;
 use clap::Parser;
@@ -13,6 +15,7 @@ use colored::Colorize;
 use config::Args;
 use config::Config;
 use config::span_dump;
+use geometry::atmosphere::Atmosphere;
 use geometry::bvh::BvhNode;
 use std::fs;
 
@@ -62,8 +65,18 @@ fn main() {
 
     let camera = Camera::new(config.camera);
     let world = BvhNode::geometry(config.objects);
+    let world = match config.atmosphere {
+        Some(atmosphere) => Atmosphere::geometry(
+            world,
+            atmosphere.density,
+            atmosphere.falloff,
+            atmosphere.height,
+            atmosphere.phase_function,
+        ),
+        None => world,
+    };
 
-    match camera.render(&world).save(args.output) {
+    match camera.render(&world, &config.lights).save(args.output) {
         Ok(_) => println!("Image saved successfully."),
         Err(e) => println!("Error saving image: {}", e),
     }
@@ -0,0 +1,93 @@
+use crate::geometry::HitRecord;
+use crate::geometry::frame::Frame;
+use crate::material::Material;
+use crate::material::Surface;
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use crate::math;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::Rng;
+use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+/// A medium scattering light unevenly around its direction of travel, following a blend of two
+/// Henyey-Greenstein lobes: most light goes to the lobe of asymmetry `g`, and `back_weight` of it
+/// to the lobe of asymmetry `back_g`.
+#[derive(Debug, Clone)]
+pub struct Anisotropic {
+    pub texture: Texture,
+    pub emission: Texture,
+    pub g: f64,
+    pub back_g: f64,
+    pub back_weight: f64,
+}
+
+impl Anisotropic {
+    pub fn new(
+        texture: Texture,
+        emission: Texture,
+        g: f64,
+        back_g: f64,
+        back_weight: f64,
+    ) -> Anisotropic {
+        Anisotropic {
+            texture,
+            emission,
+            g,
+            back_g,
+            back_weight,
+        }
+    }
+    pub fn material(
+        texture: Texture,
+        emission: Texture,
+        g: f64,
+        back_g: f64,
+        back_weight: f64,
+    ) -> Material {
+        Material::Anisotropic(Anisotropic::new(texture, emission, g, back_g, back_weight))
+    }
+}
+
+impl Surface for Anisotropic {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Vector3<f64>,
+        scattered: &mut Ray,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        let g = match rng.random::<f64>() < self.back_weight {
+            true => self.back_g,
+            false => self.g,
+        };
+        let cos_theta = math::sample_henyey_greenstein(g, rng);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * rng.random::<f64>();
+
+        let frame = Frame::new(record.point, r_in.direction);
+        scattered.origin = record.point;
+        scattered.time = r_in.time;
+        scattered.direction = frame.vector_to_world(&Vector3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+        attenuation.copy_from(&self.texture.sample(record.u, record.v, record.point));
+        true
+    }
+
+    fn emitted(&self, _: &Ray, record: &HitRecord) -> Vector3<f64> {
+        self.emission.sample(record.u, record.v, record.point)
+    }
+
+    fn evaluate(&self, r_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
+        let cos_theta = r_in.direction.normalize().dot(&direction.normalize());
+        let phase = (1.0 - self.back_weight) * math::henyey_greenstein(self.g, cos_theta)
+            + self.back_weight * math::henyey_greenstein(self.back_g, cos_theta);
+        self.texture.sample(record.u, record.v, record.point) * phase
+    }
+}
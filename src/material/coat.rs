@@ -0,0 +1,160 @@
+use crate::geometry::HitRecord;
+use crate::geometry::frame::Frame;
+use crate::material::Material;
+use crate::material::Surface;
+use crate::material::microfacet::Ggx;
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use crate::math::reflect;
+use crate::math::reflectance;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::prelude::*;
+use rand::rngs::ThreadRng;
+use std::fmt::Debug;
+
+/// A thin dielectric layer, like varnish or lacquer, over a `base` material. Light reflects off
+/// the coat by its Fresnel reflectance and otherwise reaches the base, tinted by the coat's
+/// `color` on the way.
+#[derive(Debug, Clone)]
+pub struct Coat {
+    pub base: Box<Material>,
+    pub refraction_index: f64,
+    pub distribution: Ggx,
+    pub color: Texture,
+}
+
+impl Coat {
+    pub fn material(
+        base: Material,
+        refraction_index: f64,
+        roughness: f64,
+        color: Texture,
+    ) -> Material {
+        Material::Coat(Coat {
+            base: Box::new(base),
+            refraction_index,
+            distribution: Ggx::new(roughness),
+            color,
+        })
+    }
+
+    /// The share of light the coat reflects when seen at an angle with a cosine of `cos_theta`
+    /// to its normal, taking the surface as flat.
+    fn reflectance(&self, cos_theta: f64) -> f64 {
+        reflectance(cos_theta.clamp(0.0, 1.0), self.refraction_index)
+    }
+
+    fn color(&self, record: &HitRecord) -> Vector3<f64> {
+        self.color.sample(record.u, record.v, record.point)
+    }
+}
+
+impl Surface for Coat {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Vector3<f64>,
+        scattered: &mut Ray,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        let frame = Frame::new(record.point, record.normal);
+        let wo = frame.vector_to_local(&-r_in.direction.normalize());
+
+        // The coat is picked by its reflectance seen head on from `wo`, as `evaluate` weighs the
+        // base, and the reflectance of the sampled microfacet makes up the difference.
+        let chance = self.reflectance(wo.z);
+        if chance > rng.random::<f64>() {
+            let wm = self.distribution.sample_normal(&wo, rng);
+            let wi = reflect(&-wo, &wm);
+            if wi.z <= 0.0 {
+                return false;
+            }
+
+            let cos_theta = f64::min(wo.dot(&wm), 1.0);
+            let weight = reflectance(cos_theta, self.refraction_index) / chance
+                * self.distribution.weight(&wo, &wi);
+            scattered.origin = record.point;
+            scattered.time = r_in.time;
+            scattered.direction = frame.vector_to_world(&wi);
+            attenuation.copy_from(&Vector3::from_element(weight));
+            return true;
+        }
+
+        if !self.base.scatter(r_in, record, attenuation, scattered, rng) {
+            return false;
+        }
+        attenuation.component_mul_assign(&self.color(record));
+        true
+    }
+
+    /// Light given off by the base passes through the coat like light reaching the base.
+    fn emitted(&self, r_in: &Ray, record: &HitRecord) -> Vector3<f64> {
+        let cos_theta = -r_in.direction.normalize().dot(&record.normal);
+        let transmitted = 1.0 - self.reflectance(cos_theta);
+        self.base
+            .emitted(r_in, record)
+            .component_mul(&self.color(record))
+            * transmitted
+    }
+
+    fn evaluate(&self, r_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
+        let frame = Frame::new(record.point, record.normal);
+        let wo = frame.vector_to_local(&-r_in.direction.normalize());
+        let wi = frame.vector_to_local(&direction.normalize());
+
+        // Light reaches the base as often as the coat does not reflect it.
+        let coat = match self.distribution.reflection(&wo, &wi) {
+            reflection if reflection > 0.0 => {
+                let cos_theta = wo.dot(&(wo + wi).normalize());
+                reflectance(cos_theta, self.refraction_index) * reflection
+            }
+            _ => 0.0,
+        };
+        let transmitted = 1.0 - self.reflectance(wo.z);
+        Vector3::from_element(coat)
+            + self
+                .base
+                .evaluate(r_in, record, direction)
+                .component_mul(&self.color(record))
+                * transmitted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::light::Light;
+    use crate::material::tests::assert_scatter_matches_evaluate;
+    use crate::material::tests::facing_up;
+    use crate::material::texture::SolidColor;
+
+    #[test]
+    fn test_scatter_matches_evaluate() {
+        let base = Lambertian::material(SolidColor::texture(Vector3::from_element(0.8)));
+        let color = SolidColor::texture(Vector3::from_element(1.0));
+        assert_scatter_matches_evaluate(&Coat::material(base, 1.5, 0.3, color));
+    }
+
+    #[test]
+    fn test_emission_passes_through_coat() {
+        let emission = Vector3::new(1.0, 2.0, 3.0);
+        let light = Light::material(SolidColor::texture(emission));
+        let color = Vector3::new(0.5, 1.0, 1.0);
+        let coat = Coat::material(light, 1.5, 0.0, SolidColor::texture(color));
+
+        let (record, rays) = facing_up(&[1.0, 0.1]);
+        let head_on = coat.emitted(&rays[0], &record);
+        // A 1.5 index coat reflects 4% of light head on.
+        let expected = emission.component_mul(&color) * 0.96;
+        assert!(
+            (head_on - expected).norm() < 1e-9,
+            "{head_on} != {expected}"
+        );
+        // And more of it towards grazing angles.
+        let grazing = coat.emitted(&rays[1], &record);
+        assert!(grazing.x < head_on.x);
+    }
+}
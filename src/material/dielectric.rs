@@ -1,23 +1,159 @@
 use crate::geometry::HitRecord;
+use crate::geometry::frame::Frame;
 use crate::material::Material;
 use crate::material::Surface;
+use crate::material::medium::Medium;
+use crate::material::medium::MediumStack;
+use crate::material::microfacet::Ggx;
+use crate::material::thin_film::ThinFilm;
 use crate::math::reflect;
 use crate::math::reflectance;
 use crate::math::refract;
 use crate::ray::Ray;
+use nalgebra::Complex;
 use nalgebra::Vector3;
 use rand::prelude::*;
 use rand::rngs::ThreadRng;
 use std::fmt::Debug;
 
+/// How the refraction index of a dielectric changes with the wavelength of light, which splits
+/// white light into a rainbow when rendering spectrally.
+#[derive(Debug, Clone)]
+pub enum Dispersion {
+    None,
+    /// n = a + b / λ², with λ in micrometers.
+    Cauchy {
+        a: f64,
+        b: f64,
+    },
+    /// n² = 1 + Σ bᵢ λ² / (λ² - cᵢ), with λ in micrometers.
+    Sellmeier {
+        b: [f64; 3],
+        c: [f64; 3],
+    },
+}
+
+impl Dispersion {
+    /// The refraction index at a `wavelength` in nanometers, if it depends on it.
+    pub fn refraction_index(&self, wavelength: f64) -> Option<f64> {
+        let l2 = (wavelength / 1000.0).powi(2);
+        match self {
+            Dispersion::None => None,
+            Dispersion::Cauchy { a, b } => Some(a + b / l2),
+            Dispersion::Sellmeier { b, c } => Some(
+                (1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f64>())
+                    .max(1.0)
+                    .sqrt(),
+            ),
+        }
+    }
+}
+
+/// A transparent material, whose inside is a `Medium` and whose surface is frosted when its
+/// microfacet `distribution` is wide. Where dielectrics overlap, such as water filling a glass,
+/// the one with the lower priority is ignored and light only bends at the surfaces of the one
+/// that fills the space. The surface can be covered by a thin film.
 #[derive(Debug, Clone)]
 pub struct Dielectric {
-    pub refraction_index: f64,
+    pub medium: Medium,
+    pub dispersion: Dispersion,
+    pub distribution: Ggx,
+    pub film: Option<ThinFilm>,
 }
 
 impl Dielectric {
     pub fn material(refraction_index: f64) -> Material {
-        Material::Dielectric(Dielectric { refraction_index })
+        Dielectric::medium(
+            refraction_index,
+            Dispersion::None,
+            Vector3::default(),
+            0,
+            0.0,
+            None,
+        )
+    }
+
+    pub fn medium(
+        refraction_index: f64,
+        dispersion: Dispersion,
+        absorption: Vector3<f64>,
+        priority: u32,
+        roughness: f64,
+        film: Option<ThinFilm>,
+    ) -> Material {
+        Material::Dielectric(Dielectric {
+            medium: Medium {
+                refraction_index,
+                absorption,
+                priority,
+                ..Default::default()
+            },
+            dispersion,
+            distribution: Ggx::new(roughness),
+            film,
+        })
+    }
+
+    /// The media a ray travels through after crossing the surface, and the refraction indices on
+    /// the side the ray comes from and the side it goes to. The indices are `None` when a
+    /// dielectric with a higher priority surrounds the surface, which is then passed straight
+    /// through.
+    fn cross(&self, r_in: &Ray, entering: bool) -> (MediumStack, Option<(f64, f64)>) {
+        // A path keeps its wavelength, so the media it enters always bend it the same way.
+        let refraction_index = r_in
+            .wavelength
+            .and_then(|wavelength| self.dispersion.refraction_index(wavelength))
+            .unwrap_or(self.medium.refraction_index);
+        let medium = Medium {
+            refraction_index,
+            ..self.medium
+        };
+
+        let mut media = r_in.media;
+        match entering {
+            true => media.push(medium),
+            false => media.remove(&medium),
+        }
+
+        // The medium on the other side of the surface from this one.
+        let surrounding = match entering {
+            true => r_in.media.current(),
+            false => media.current(),
+        };
+
+        // Surfaces inside of a dielectric with a higher priority are passed straight through.
+        if surrounding.is_some_and(|surrounding| surrounding.priority > medium.priority) {
+            return (media, None);
+        }
+
+        let outside = surrounding.map_or(1.0, |surrounding| surrounding.refraction_index);
+        let indices = match entering {
+            true => (outside, medium.refraction_index),
+            false => (medium.refraction_index, outside),
+        };
+        (media, Some(indices))
+    }
+
+    /// The share of light reflected off a microfacet seen at an angle with a cosine of
+    /// `cos_theta`, going from a refraction index of `from` to one of `to`.
+    fn fresnel(
+        &self,
+        r_in: &Ray,
+        record: &HitRecord,
+        cos_theta: f64,
+        (from, to): (f64, f64),
+    ) -> Vector3<f64> {
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        if from / to * sin_theta > 1.0 {
+            return Vector3::from_element(1.0);
+        }
+        match &self.film {
+            Some(film) => {
+                let substrate = [Complex::from(to); 3];
+                film.reflectance(record, r_in.wavelength, cos_theta, from, &substrate)
+            }
+            None => Vector3::from_element(reflectance(cos_theta, from / to)),
+        }
     }
 }
 
@@ -31,30 +167,69 @@ impl Surface for Dielectric {
         rng: &mut ThreadRng,
     ) -> bool {
         attenuation.copy_from(&Vector3::from_element(1.0));
-        let r_index = match record.front_face {
-            true => 1.0 / self.refraction_index,
-            false => self.refraction_index,
-        };
+        scattered.origin = record.point;
+        scattered.time = r_in.time;
 
-        let normalized_direction = r_in.direction.normalize();
+        let (media, indices) = self.cross(r_in, record.front_face);
+        let Some(indices) = indices else {
+            scattered.direction = r_in.direction;
+            scattered.media = media;
+            return true;
+        };
 
-        let cos_theta = f64::min((-normalized_direction).dot(&record.normal), 1.0);
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
-        let cannot_refract = r_index * sin_theta > 1.0;
+        // Light bends at a microfacet of the surface, which is the surface itself when smooth.
+        let frame = Frame::new(record.point, record.normal);
+        let wo = frame.vector_to_local(&-r_in.direction.normalize());
+        let wm = self.distribution.sample_normal(&wo, rng);
 
-        scattered.origin = record.point;
-        scattered.time = r_in.time;
+        // Light is reflected as often as it is on average over the channels, weighted by how
+        // much more or less of it each channel reflects. Without a film all channels match.
+        let cos_theta = f64::min(wo.dot(&wm), 1.0);
+        let fresnel = self.fresnel(r_in, record, cos_theta, indices);
+        let chance = fresnel.mean();
 
-        scattered.direction =
-            match cannot_refract || (reflectance(cos_theta, r_index) > rng.random::<f64>()) {
-                true => reflect(&normalized_direction, &record.normal),
-                false => refract(&normalized_direction, &record.normal, r_index),
-            };
+        let (wi, weight) = match chance > rng.random::<f64>() {
+            true => {
+                let wi = reflect(&-wo, &wm);
+                if wi.z <= 0.0 {
+                    return false;
+                }
+                (wi, fresnel / chance)
+            }
+            false => {
+                let wi = refract(&-wo, &wm, indices.0 / indices.1);
+                if wi.z >= 0.0 {
+                    return false;
+                }
+                scattered.media = media;
+                (wi, (Vector3::from_element(1.0) - fresnel) / (1.0 - chance))
+            }
+        };
 
+        scattered.direction = frame.vector_to_world(&wi);
+        attenuation.copy_from(&(weight * self.distribution.weight(&wo, &wi)));
         true
     }
 
-    fn emitted(&self, _: f64, _: f64, _: Vector3<f64>) -> Vector3<f64> {
+    fn emitted(&self, _: &Ray, _: &HitRecord) -> Vector3<f64> {
         Vector3::<f64>::default()
     }
+
+    fn evaluate(&self, r_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
+        let (_, Some(indices)) = self.cross(r_in, record.front_face) else {
+            return Vector3::default();
+        };
+
+        // Only the reflection off a rough surface is lit, light refracting through it is not.
+        let frame = Frame::new(record.point, record.normal);
+        let wo = frame.vector_to_local(&-r_in.direction.normalize());
+        let wi = frame.vector_to_local(&direction.normalize());
+        let reflection = self.distribution.reflection(&wo, &wi);
+        if reflection <= 0.0 {
+            return Vector3::default();
+        }
+
+        let cos_theta = f64::min(wo.dot(&(wo + wi).normalize()), 1.0);
+        self.fresnel(r_in, record, cos_theta, indices) * reflection
+    }
 }
@@ -7,19 +7,21 @@ use crate::math;
 use crate::ray::Ray;
 use nalgebra::Vector3;
 use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
 use std::fmt::Debug;
 
 #[derive(Debug, Clone)]
 pub struct Isotropic {
     pub texture: Texture,
+    pub emission: Texture,
 }
 
 impl Isotropic {
-    pub fn new(texture: Texture) -> Isotropic {
-        Isotropic { texture }
+    pub fn new(texture: Texture, emission: Texture) -> Isotropic {
+        Isotropic { texture, emission }
     }
-    pub fn material(texture: Texture) -> Material {
-        Material::Isotropic(Isotropic::new(texture))
+    pub fn material(texture: Texture, emission: Texture) -> Material {
+        Material::Isotropic(Isotropic::new(texture, emission))
     }
 }
 
@@ -39,7 +41,11 @@ impl Surface for Isotropic {
         true
     }
 
-    fn emitted(&self, _: f64, _: f64, _: Vector3<f64>) -> Vector3<f64> {
-        Vector3::<f64>::default()
+    fn emitted(&self, _: &Ray, record: &HitRecord) -> Vector3<f64> {
+        self.emission.sample(record.u, record.v, record.point)
+    }
+
+    fn evaluate(&self, _: &Ray, record: &HitRecord, _: &Vector3<f64>) -> Vector3<f64> {
+        self.texture.sample(record.u, record.v, record.point) / (4.0 * PI)
     }
 }
@@ -8,6 +8,7 @@ use crate::math::near_zero;
 use crate::ray::Ray;
 use nalgebra::Vector3;
 use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
 use std::fmt::Debug;
 
 #[derive(Debug, Clone)]
@@ -42,7 +43,12 @@ impl Surface for Lambertian {
         true
     }
 
-    fn emitted(&self, _: f64, _: f64, _: Vector3<f64>) -> Vector3<f64> {
+    fn emitted(&self, _: &Ray, _: &HitRecord) -> Vector3<f64> {
         Vector3::<f64>::default()
     }
+
+    fn evaluate(&self, _: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
+        let cos_theta = record.normal.dot(&direction.normalize()).max(0.0);
+        self.texture.sample(record.u, record.v, record.point) * cos_theta / PI
+    }
 }
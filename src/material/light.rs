@@ -8,14 +8,26 @@ use nalgebra::Vector3;
 use rand::rngs::ThreadRng;
 use std::fmt::Debug;
 
+/// An emitter whose radiance is its `texture` scaled by `intensity`. One-sided lights only emit
+/// from the front of the surface, which is the outside of closed shapes.
 #[derive(Debug, Clone)]
 pub struct Light {
     pub texture: Texture,
+    pub intensity: Vector3<f64>,
+    pub two_sided: bool,
 }
 
 impl Light {
     pub fn material(texture: Texture) -> Material {
-        Material::Light(Light { texture })
+        Light::emitter(texture, Vector3::from_element(1.0), true)
+    }
+
+    pub fn emitter(texture: Texture, intensity: Vector3<f64>, two_sided: bool) -> Material {
+        Material::Light(Light {
+            texture,
+            intensity,
+            two_sided,
+        })
     }
 }
 
@@ -31,7 +43,16 @@ impl Surface for Light {
         false
     }
 
-    fn emitted(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
-        self.texture.sample(u, v, p)
+    fn emitted(&self, _: &Ray, record: &HitRecord) -> Vector3<f64> {
+        if !self.two_sided && !record.front_face {
+            return Vector3::default();
+        }
+        self.texture
+            .sample(record.u, record.v, record.point)
+            .component_mul(&self.intensity)
+    }
+
+    fn evaluate(&self, _: &Ray, _: &HitRecord, _: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::<f64>::default()
     }
 }
@@ -0,0 +1,155 @@
+use nalgebra::Vector3;
+use rand::Rng;
+
+/// Media a ray can be inside of at once, any deeper nesting is ignored.
+const MAX_MEDIA: usize = 4;
+
+/// The inside of a dielectric, which light passing through is absorbed by at a rate of
+/// `absorption` per unit of distance. Light is also scattered at a rate of `scattering`, in
+/// directions following a Henyey-Greenstein lobe of asymmetry `anisotropy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Medium {
+    pub refraction_index: f64,
+    pub absorption: Vector3<f64>,
+    pub scattering: Vector3<f64>,
+    pub anisotropy: f64,
+    pub priority: u32,
+}
+
+impl Medium {
+    /// The rate at which light is either absorbed or scattered.
+    pub fn extinction(&self) -> Vector3<f64> {
+        self.absorption + self.scattering
+    }
+}
+
+/// The dielectrics a ray is inside of. Where they overlap, the one with the highest priority
+/// fills the space, ties going to the one entered last.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MediumStack {
+    media: [Medium; MAX_MEDIA],
+    len: usize,
+}
+
+impl MediumStack {
+    pub fn current(&self) -> Option<&Medium> {
+        self.media[..self.len]
+            .iter()
+            .max_by_key(|medium| medium.priority)
+    }
+
+    pub fn push(&mut self, medium: Medium) {
+        if self.len < MAX_MEDIA {
+            self.media[self.len] = medium;
+            self.len += 1;
+        }
+    }
+
+    pub fn remove(&mut self, medium: &Medium) {
+        if let Some(index) = self.media[..self.len].iter().rposition(|m| m == medium) {
+            self.media.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+    }
+
+    /// The fraction of light left after travelling `distance` through the current medium.
+    pub fn transmittance(&self, distance: f64) -> Vector3<f64> {
+        let extinction = self.current().map(|medium| medium.extinction());
+        extinction
+            .unwrap_or_default()
+            .map(|a| if a > 0.0 { (-a * distance).exp() } else { 1.0 })
+    }
+
+    /// Samples how far a ray gets through the current medium before it scatters, giving `None`
+    /// when it travels the whole `distance` without scattering, along with the weight of the
+    /// path up to there. Each color channel scatters at its own rate, so distances are sampled
+    /// for one channel and weighted by how likely every channel was to pick them over the whole
+    /// path, which `channel_pdfs` keeps track of.
+    pub fn sample_scattering<T: Rng>(
+        &self,
+        distance: f64,
+        channel_pdfs: &mut Vector3<f64>,
+        rng: &mut T,
+    ) -> (Option<f64>, Vector3<f64>) {
+        let Some(medium) = self
+            .current()
+            .filter(|medium| medium.scattering.max() > 0.0)
+        else {
+            return (None, self.transmittance(distance));
+        };
+
+        // Channels are picked by how likely they were to pick the path so far, which keeps
+        // channels that scatter much less than the others from making the weights blow up.
+        let extinction = medium.extinction();
+        let pick = rng.random::<f64>() * channel_pdfs.sum();
+        let channel = match pick < channel_pdfs.x {
+            true => 0,
+            false if pick < channel_pdfs.x + channel_pdfs.y => 1,
+            false => 2,
+        };
+        let sampled = -(1.0 - rng.random::<f64>()).ln() / extinction[channel];
+
+        let (collision, transmittance, pdfs) = match sampled < distance {
+            true => {
+                let transmittance = self.transmittance(sampled);
+                let pdfs = extinction.component_mul(&transmittance);
+                (Some(sampled), transmittance, pdfs)
+            }
+            false => {
+                let transmittance = self.transmittance(distance);
+                (None, transmittance, transmittance)
+            }
+        };
+
+        let pdf = channel_pdfs.component_mul(&pdfs).mean() / channel_pdfs.mean();
+        if pdf <= 0.0 {
+            return (None, Vector3::default());
+        }
+        *channel_pdfs = channel_pdfs.component_mul(&pdfs) / (pdf * channel_pdfs.mean());
+
+        let weight = match collision {
+            Some(_) => medium.scattering.component_mul(&transmittance),
+            None => transmittance,
+        };
+        (collision, weight / pdf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_sample_scattering_passes_transmittance() {
+        let mut media = MediumStack::default();
+        media.push(Medium {
+            refraction_index: 1.4,
+            absorption: Vector3::new(0.1, 0.5, 1.0),
+            scattering: Vector3::new(2.0, 1.0, 0.5),
+            ..Default::default()
+        });
+
+        // Paths that get through unscattered carry exactly the light that is not attenuated.
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let count = 200_000;
+        let distance = 0.5;
+        let passed = (0..count)
+            .map(|_| {
+                let mut channel_pdfs = Vector3::from_element(1.0);
+                match media.sample_scattering(distance, &mut channel_pdfs, &mut rng) {
+                    (None, weight) => weight,
+                    (Some(_), _) => Vector3::default(),
+                }
+            })
+            .sum::<Vector3<f64>>()
+            / count as f64;
+
+        let expected = media.transmittance(distance);
+        assert!(
+            (passed - expected).abs().max() < 0.01,
+            "{passed} != {expected}"
+        );
+    }
+}
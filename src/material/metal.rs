@@ -1,22 +1,123 @@
 use crate::geometry::HitRecord;
+use crate::geometry::frame::Frame;
 use crate::material::Material;
 use crate::material::Surface;
-use crate::math;
+use crate::material::microfacet::Ggx;
+use crate::material::microfacet::fresnel_conductor;
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use crate::material::thin_film::ThinFilm;
 use crate::math::reflect;
 use crate::ray::Ray;
+use nalgebra::Complex;
 use nalgebra::Vector3;
 use rand::rngs::ThreadRng;
 use std::fmt::Debug;
 
+/// How much light a metal reflects depending on the angle it is seen from.
+#[derive(Debug, Clone)]
+pub enum Reflectance {
+    /// Schlick's approximation, reflecting `albedo` head on and more towards grazing angles.
+    Albedo(Vector3<f64>),
+    /// The exact response of a metal with the complex refraction index `eta + ik` per channel.
+    Complex { eta: Vector3<f64>, k: Vector3<f64> },
+}
+
+impl Reflectance {
+    pub fn fresnel(&self, cos_theta: f64) -> Vector3<f64> {
+        match self {
+            Reflectance::Albedo(albedo) => {
+                albedo + (Vector3::from_element(1.0) - albedo) * (1.0 - cos_theta).powi(5)
+            }
+            Reflectance::Complex { eta, k } => eta.zip_map(k, |eta, k| {
+                fresnel_conductor(cos_theta.clamp(0.0, 1.0), eta, k)
+            }),
+        }
+    }
+
+    /// The complex refraction index of each channel. An `albedo` is taken as the reflectance
+    /// head on of a dielectric, which has a real index.
+    pub fn refraction_index(&self) -> [Complex<f64>; 3] {
+        match self {
+            Reflectance::Albedo(albedo) => [0, 1, 2].map(|channel| {
+                let r = albedo[channel].clamp(0.0, 0.99).sqrt();
+                Complex::from((1.0 + r) / (1.0 - r))
+            }),
+            Reflectance::Complex { eta, k } => {
+                [0, 1, 2].map(|channel| Complex::new(eta[channel], k[channel]))
+            }
+        }
+    }
+}
+
+/// What a metal is made of, either an `albedo` that can vary over its surface or a complex
+/// refraction index.
+#[derive(Debug, Clone)]
+pub enum Conductor {
+    Albedo(Texture),
+    Complex { eta: Vector3<f64>, k: Vector3<f64> },
+}
+
+impl Conductor {
+    fn reflectance(&self, record: &HitRecord) -> Reflectance {
+        match self {
+            Conductor::Albedo(albedo) => {
+                Reflectance::Albedo(albedo.sample(record.u, record.v, record.point))
+            }
+            Conductor::Complex { eta, k } => Reflectance::Complex { eta: *eta, k: *k },
+        }
+    }
+}
+
+/// A conductor whose surface is made of microfacets following the GGX distribution, optionally
+/// covered by a thin film.
 #[derive(Debug, Clone)]
 pub struct Metal {
-    pub albedo: Vector3<f64>,
-    pub roughness: f64,
+    pub conductor: Conductor,
+    pub distribution: Ggx,
+    pub film: Option<ThinFilm>,
 }
 
 impl Metal {
-    pub fn material(albedo: Vector3<f64>, roughness: f64) -> Material {
-        Material::Metal(Metal { albedo, roughness })
+    pub fn material(albedo: Texture, roughness: f64, film: Option<ThinFilm>) -> Material {
+        Material::Metal(Metal {
+            conductor: Conductor::Albedo(albedo),
+            distribution: Ggx::new(roughness),
+            film,
+        })
+    }
+
+    pub fn conductor(
+        eta: Vector3<f64>,
+        k: Vector3<f64>,
+        roughness: f64,
+        film: Option<ThinFilm>,
+    ) -> Material {
+        Material::Metal(Metal {
+            conductor: Conductor::Complex { eta, k },
+            distribution: Ggx::new(roughness),
+            film,
+        })
+    }
+
+    /// The share of light reflected off a microfacet seen at an angle with a cosine of
+    /// `cos_theta`.
+    fn fresnel(&self, r_in: &Ray, record: &HitRecord, cos_theta: f64) -> Vector3<f64> {
+        let reflectance = self.conductor.reflectance(record);
+        let Some(film) = &self.film else {
+            return reflectance.fresnel(cos_theta);
+        };
+        let outside = r_in
+            .media
+            .current()
+            .map_or(1.0, |medium| medium.refraction_index);
+        film.reflectance(
+            record,
+            r_in.wavelength,
+            cos_theta,
+            outside,
+            &reflectance.refraction_index(),
+        )
     }
 }
 
@@ -29,17 +130,35 @@ impl Surface for Metal {
         scattered: &mut Ray,
         rng: &mut ThreadRng,
     ) -> bool {
-        let mut reflected = reflect(&r_in.direction, &record.normal);
-        reflected = reflected.normalize() + (math::random_normal(rng) * self.roughness);
+        let frame = Frame::new(record.point, record.normal);
+        let wo = frame.vector_to_local(&-r_in.direction.normalize());
+        let wm = self.distribution.sample_normal(&wo, rng);
+        let wi = reflect(&-wo, &wm);
+        if wi.z <= 0.0 {
+            return false;
+        }
 
         scattered.origin = record.point;
         scattered.time = r_in.time;
-        scattered.direction = reflected;
-        attenuation.copy_from(&self.albedo);
-        scattered.direction.dot(&record.normal) > 0.0
+        scattered.direction = frame.vector_to_world(&wi);
+        attenuation.copy_from(
+            &(self.fresnel(r_in, record, wo.dot(&wm)) * self.distribution.weight(&wo, &wi)),
+        );
+        true
     }
 
-    fn emitted(&self, _: f64, _: f64, _: Vector3<f64>) -> Vector3<f64> {
+    fn emitted(&self, _: &Ray, _: &HitRecord) -> Vector3<f64> {
         Vector3::<f64>::default()
     }
+
+    fn evaluate(&self, r_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
+        let frame = Frame::new(record.point, record.normal);
+        let wo = frame.vector_to_local(&-r_in.direction.normalize());
+        let wi = frame.vector_to_local(&direction.normalize());
+        let reflection = self.distribution.reflection(&wo, &wi);
+        if reflection <= 0.0 {
+            return Vector3::default();
+        }
+        self.fresnel(r_in, record, wo.dot(&(wo + wi).normalize())) * reflection
+    }
 }
@@ -0,0 +1,180 @@
+use nalgebra::Vector3;
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// Roughness below which a surface is treated as a perfect mirror.
+const SMOOTH: f64 = 1e-4;
+
+/// The GGX (Trowbridge-Reitz) distribution of microfacet normals, in a space where the surface
+/// normal is the z axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Ggx {
+    pub alpha: f64,
+}
+
+impl Ggx {
+    /// Roughness is squared into the width of the distribution, so it changes evenly to the eye.
+    pub fn new(roughness: f64) -> Self {
+        Ggx {
+            alpha: roughness * roughness,
+        }
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < SMOOTH
+    }
+
+    /// Smith's auxiliary function, the share of microfacets hidden when seen from `w`.
+    pub fn lambda(&self, w: &Vector3<f64>) -> f64 {
+        let cos2 = w.z * w.z;
+        if cos2 <= 0.0 {
+            return f64::INFINITY;
+        }
+        let tan2 = (1.0 - cos2) / cos2;
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// The density of microfacets facing `wm`.
+    pub fn d(&self, wm: &Vector3<f64>) -> f64 {
+        let alpha2 = self.alpha * self.alpha;
+        let denominator = wm.z * wm.z * (alpha2 - 1.0) + 1.0;
+        alpha2 / (PI * denominator * denominator)
+    }
+
+    /// The share of light arriving from `wi` that microfacets reflect towards `wo`, times the
+    /// cosine of `wi` and without the Fresnel term. A smooth surface only reflects the mirror
+    /// direction, which no other direction hits, so it is zero.
+    pub fn reflection(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        if self.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).normalize();
+        self.d(&wm) / (4.0 * wo.z * (1.0 + self.lambda(wo) + self.lambda(wi)))
+    }
+
+    /// The weight of light leaving towards `wi` for a normal picked by `sample_normal` from `wo`,
+    /// which is the height-correlated masking-shadowing divided by the masking from `wo`.
+    pub fn weight(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        let lambda_o = self.lambda(wo);
+        (1.0 + lambda_o) / (1.0 + lambda_o + self.lambda(wi))
+    }
+
+    /// Picks a microfacet normal visible from `wo`.
+    ///
+    /// Heitz, "Sampling the GGX Distribution of Visible Normals"
+    pub fn sample_normal<T: Rng>(&self, wo: &Vector3<f64>, rng: &mut T) -> Vector3<f64> {
+        if self.is_smooth() {
+            return Vector3::z();
+        }
+
+        // Stretch the view so the distribution becomes a hemisphere.
+        let vh = Vector3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z).normalize();
+        let lensq = vh.x * vh.x + vh.y * vh.y;
+        let t1 = match lensq > 0.0 {
+            true => Vector3::new(-vh.y, vh.x, 0.0) / lensq.sqrt(),
+            false => Vector3::x(),
+        };
+        let t2 = vh.cross(&t1);
+
+        // Sample the projected area of the hemisphere, squashed towards the visible half.
+        let r = rng.random::<f64>().sqrt();
+        let phi = 2.0 * PI * rng.random::<f64>();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        Vector3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(1e-6)).normalize()
+    }
+}
+
+/// The share of light reflected by a metal with the complex refraction index `eta + ik`, for
+/// light arriving at an angle with a cosine of `cos_theta`.
+pub fn fresnel_conductor(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta * cos_theta;
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * cos_theta * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rs + rp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_ggx_sample_normal_visible() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        let ggx = Ggx::new(0.7);
+        let wo = Vector3::new(0.8, 0.1, 0.3).normalize();
+        for _ in 0..1000 {
+            let wm = ggx.sample_normal(&wo, &mut rng);
+            assert!((wm.norm() - 1.0).abs() < 1e-9);
+            assert!(wm.z > 0.0);
+            assert!(wm.dot(&wo) > -1e-9);
+        }
+    }
+
+    #[test]
+    fn test_ggx_smooth() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        let ggx = Ggx::new(0.0);
+        let wo = Vector3::new(0.6, 0.0, 0.8);
+        assert_eq!(ggx.sample_normal(&wo, &mut rng), Vector3::z());
+        assert_eq!(ggx.weight(&wo, &Vector3::new(-0.6, 0.0, 0.8)), 1.0);
+    }
+
+    #[test]
+    fn test_ggx_reflection_matches_sampling() {
+        // Sampled weights and evaluated reflection estimate the same share of reflected light.
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        let ggx = Ggx::new(0.5);
+        let wo = Vector3::new(0.6, 0.0, 0.8);
+        let count = 200000;
+
+        let sampled = (0..count)
+            .map(|_| {
+                let wm = ggx.sample_normal(&wo, &mut rng);
+                let wi = 2.0 * wo.dot(&wm) * wm - wo;
+                if wi.z <= 0.0 {
+                    0.0
+                } else {
+                    ggx.weight(&wo, &wi)
+                }
+            })
+            .sum::<f64>()
+            / count as f64;
+
+        let evaluated = (0..count)
+            .map(|_| {
+                let (u1, u2) = (rng.random::<f64>(), rng.random::<f64>());
+                let r = (1.0 - u1 * u1).sqrt();
+                let phi = 2.0 * PI * u2;
+                let wi = Vector3::new(r * phi.cos(), r * phi.sin(), u1);
+                ggx.reflection(&wo, &wi) * 2.0 * PI
+            })
+            .sum::<f64>()
+            / count as f64;
+
+        assert!((sampled - evaluated).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_fresnel_conductor() {
+        // A perfect conductor reflects everything, a dielectric matches its normal reflectance.
+        assert!((fresnel_conductor(0.5, 1.0, 1e9) - 1.0).abs() < 1e-6);
+        assert!((fresnel_conductor(1.0, 1.5, 0.0) - 0.04).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::geometry::HitRecord;
+use crate::material::Material;
+use crate::material::Surface;
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::prelude::*;
+use rand::rngs::ThreadRng;
+use std::fmt::Debug;
+
+/// Blends material `a` into material `b` by a `factor` from 0 to 1, which can be a texture used
+/// as a mask. Each scatter picks one of the two at random by how much of it there is.
+#[derive(Debug, Clone)]
+pub struct Mix {
+    pub a: Box<Material>,
+    pub b: Box<Material>,
+    pub factor: Texture,
+}
+
+impl Mix {
+    pub fn material(a: Material, b: Material, factor: Texture) -> Material {
+        Material::Mix(Mix {
+            a: Box::new(a),
+            b: Box::new(b),
+            factor,
+        })
+    }
+
+    fn factor(&self, u: f64, v: f64, p: Vector3<f64>) -> f64 {
+        self.factor.sample(u, v, p).mean().clamp(0.0, 1.0)
+    }
+}
+
+impl Surface for Mix {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Vector3<f64>,
+        scattered: &mut Ray,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        let material = match self.factor(record.u, record.v, record.point) > rng.random::<f64>() {
+            true => &self.b,
+            false => &self.a,
+        };
+        material.scatter(r_in, record, attenuation, scattered, rng)
+    }
+
+    fn emitted(&self, r_in: &Ray, record: &HitRecord) -> Vector3<f64> {
+        let factor = self.factor(record.u, record.v, record.point);
+        self.a.emitted(r_in, record) * (1.0 - factor) + self.b.emitted(r_in, record) * factor
+    }
+
+    fn evaluate(&self, r_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
+        let factor = self.factor(record.u, record.v, record.point);
+        self.a.evaluate(r_in, record, direction) * (1.0 - factor)
+            + self.b.evaluate(r_in, record, direction) * factor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use crate::material::light::Light;
+    use crate::material::metal::Metal;
+    use crate::material::tests::assert_scatter_matches_evaluate;
+    use crate::material::tests::facing_up;
+    use crate::material::texture::SolidColor;
+
+    fn constant(value: f64) -> Texture {
+        SolidColor::texture(Vector3::from_element(value))
+    }
+
+    #[test]
+    fn test_scatter_matches_evaluate() {
+        let diffuse = Lambertian::material(constant(0.2));
+        let metal = Metal::material(SolidColor::texture(Vector3::from_element(0.9)), 0.5, None);
+        assert_scatter_matches_evaluate(&Mix::material(diffuse, metal, constant(0.3)));
+    }
+
+    #[test]
+    fn test_emission_is_blended() {
+        let light = Light::material(constant(2.0));
+        let diffuse = Lambertian::material(constant(0.5));
+        let mix = Mix::material(light, diffuse, constant(0.25));
+
+        let (record, rays) = facing_up(&[1.0]);
+        assert_eq!(mix.emitted(&rays[0], &record), Vector3::from_element(1.5));
+    }
+}
@@ -1,16 +1,29 @@
+pub mod anisotropic;
+pub mod coat;
 pub mod dielectric;
 pub mod isotropic;
 pub mod lambertian;
 pub mod light;
+pub mod medium;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod principled;
+pub mod subsurface;
 pub mod texture;
+pub mod thin_film;
 
 use crate::geometry::HitRecord;
+use crate::material::anisotropic::Anisotropic;
+use crate::material::coat::Coat;
 use crate::material::dielectric::Dielectric;
 use crate::material::isotropic::Isotropic;
 use crate::material::lambertian::Lambertian;
 use crate::material::light::Light;
 use crate::material::metal::Metal;
+use crate::material::mix::Mix;
+use crate::material::principled::Principled;
+use crate::material::subsurface::Subsurface;
 use crate::ray::Ray;
 use nalgebra::Vector3;
 use rand::rngs::ThreadRng;
@@ -25,7 +38,10 @@ pub trait Surface {
         scattered: &mut Ray,
         rng: &mut ThreadRng,
     ) -> bool;
-    fn emitted(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64>;
+    fn emitted(&self, ray_in: &Ray, record: &HitRecord) -> Vector3<f64>;
+    /// The light arriving from `direction` that is scattered back along `ray_in`, as the BSDF
+    /// times the cosine to the normal. Perfectly specular materials scatter none of it.
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64>;
 }
 
 #[derive(Debug, Clone)]
@@ -35,6 +51,11 @@ pub enum Material {
     Lambertian(Lambertian),
     Light(Light),
     Isotropic(Isotropic),
+    Anisotropic(Anisotropic),
+    Principled(Box<Principled>),
+    Mix(Mix),
+    Coat(Coat),
+    Subsurface(Subsurface),
 }
 
 impl Surface for Material {
@@ -62,15 +83,119 @@ impl Surface for Material {
             Material::Isotropic(material) => {
                 material.scatter(ray_in, record, attenuation, scattered, rng)
             }
+            Material::Anisotropic(material) => {
+                material.scatter(ray_in, record, attenuation, scattered, rng)
+            }
+            Material::Principled(material) => {
+                material.scatter(ray_in, record, attenuation, scattered, rng)
+            }
+            Material::Mix(material) => {
+                material.scatter(ray_in, record, attenuation, scattered, rng)
+            }
+            Material::Coat(material) => {
+                material.scatter(ray_in, record, attenuation, scattered, rng)
+            }
+            Material::Subsurface(material) => {
+                material.scatter(ray_in, record, attenuation, scattered, rng)
+            }
+        }
+    }
+    fn emitted(&self, ray_in: &Ray, record: &HitRecord) -> Vector3<f64> {
+        match self {
+            Material::Metal(material) => material.emitted(ray_in, record),
+            Material::Dielectric(material) => material.emitted(ray_in, record),
+            Material::Lambertian(material) => material.emitted(ray_in, record),
+            Material::Light(material) => material.emitted(ray_in, record),
+            Material::Isotropic(material) => material.emitted(ray_in, record),
+            Material::Anisotropic(material) => material.emitted(ray_in, record),
+            Material::Principled(material) => material.emitted(ray_in, record),
+            Material::Mix(material) => material.emitted(ray_in, record),
+            Material::Coat(material) => material.emitted(ray_in, record),
+            Material::Subsurface(material) => material.emitted(ray_in, record),
         }
     }
-    fn emitted(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+    fn evaluate(&self, ray_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
         match self {
-            Material::Metal(material) => material.emitted(u, v, p),
-            Material::Dielectric(material) => material.emitted(u, v, p),
-            Material::Lambertian(material) => material.emitted(u, v, p),
-            Material::Light(material) => material.emitted(u, v, p),
-            Material::Isotropic(material) => material.emitted(u, v, p),
+            Material::Metal(material) => material.evaluate(ray_in, record, direction),
+            Material::Dielectric(material) => material.evaluate(ray_in, record, direction),
+            Material::Lambertian(material) => material.evaluate(ray_in, record, direction),
+            Material::Light(material) => material.evaluate(ray_in, record, direction),
+            Material::Isotropic(material) => material.evaluate(ray_in, record, direction),
+            Material::Anisotropic(material) => material.evaluate(ray_in, record, direction),
+            Material::Principled(material) => material.evaluate(ray_in, record, direction),
+            Material::Mix(material) => material.evaluate(ray_in, record, direction),
+            Material::Coat(material) => material.evaluate(ray_in, record, direction),
+            Material::Subsurface(material) => material.evaluate(ray_in, record, direction),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// The light `material` reflects along `r_in` under a uniformly white sky, averaged over the
+    /// rays `scatter` sends out.
+    pub fn scattered_albedo(material: &impl Surface, record: &HitRecord, r_in: &Ray) -> f64 {
+        let mut rng = rand::rng();
+        let samples = 200_000;
+        let total: f64 = (0..samples)
+            .map(|_| {
+                let mut attenuation = Vector3::default();
+                let mut scattered = Ray::default();
+                match material.scatter(r_in, record, &mut attenuation, &mut scattered, &mut rng) {
+                    true => attenuation.mean(),
+                    false => 0.0,
+                }
+            })
+            .sum();
+        total / samples as f64
+    }
+
+    /// The same light, integrating `evaluate` over the hemisphere above a surface facing +z.
+    pub fn evaluated_albedo(material: &impl Surface, record: &HitRecord, r_in: &Ray) -> f64 {
+        let steps = 400;
+        let cell = (1.0 / steps as f64) * (2.0 * PI / steps as f64);
+        itertools::iproduct!(0..steps, 0..steps)
+            .map(|(i, j)| {
+                let cos_theta = (i as f64 + 0.5) / steps as f64;
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                let phi = 2.0 * PI * (j as f64 + 0.5) / steps as f64;
+                let wi = Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                material.evaluate(r_in, record, &wi).mean() * cell
+            })
+            .sum()
+    }
+
+    /// A hit on the front of a surface facing +z, and rays arriving at it at angles with the
+    /// cosines of `cos_thetas`.
+    pub fn facing_up(cos_thetas: &[f64]) -> (HitRecord, Vec<Ray>) {
+        let mut record = HitRecord::default();
+        record.normal = Vector3::z();
+        record.front_face = true;
+        let rays = cos_thetas
+            .iter()
+            .map(|cos_theta| {
+                let wo = Vector3::new((1.0 - cos_theta * cos_theta).sqrt(), 0.0, *cos_theta);
+                Ray::new(wo, -wo, 0.0)
+            })
+            .collect();
+        (record, rays)
+    }
+
+    /// Checks that `scatter` and `evaluate` of `material` agree on how much light it reflects.
+    pub fn assert_scatter_matches_evaluate(material: &impl Surface) {
+        let (record, rays) = facing_up(&[0.9, 0.5, 0.2]);
+        for r_in in rays {
+            let scattered = scattered_albedo(material, &record, &r_in);
+            let evaluated = evaluated_albedo(material, &record, &r_in);
+            assert!(scattered <= 1.0 + 1e-2, "{scattered}");
+            assert!(
+                (scattered - evaluated).abs() < 1e-2,
+                "cos {}: {scattered} != {evaluated}",
+                -r_in.direction.z
+            );
         }
     }
 }
@@ -0,0 +1,248 @@
+use crate::geometry::HitRecord;
+use crate::geometry::frame::Frame;
+use crate::material::Surface;
+use crate::material::metal::Reflectance;
+use crate::material::microfacet::Ggx;
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use crate::math::random_normal;
+use crate::math::reflect;
+use crate::math::reflectance;
+use crate::math::refract;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::prelude::*;
+use rand::rngs::ThreadRng;
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+/// Schlick's approximation of the share of light reflected by a surface reflecting `f0` head on.
+fn schlick(f0: f64, cos_theta: f64) -> f64 {
+    f0 + (1.0 - f0) * (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+/// A single value read from a texture, such as a grayscale roughness map.
+fn scalar(texture: &Texture, record: &HitRecord) -> f64 {
+    texture.sample(record.u, record.v, record.point).mean()
+}
+
+/// An artist friendly material in the style of Disney's principled BSDF, blending a diffuse base,
+/// a metal, glass and a clear coat on top. Every parameter is a texture, so it can vary across
+/// the surface, and each scatter picks one of the layers at random by how much it contributes.
+///
+/// Unlike a `Dielectric`, the transmissive part does not take part in nested media.
+#[derive(Debug, Clone)]
+pub struct Principled {
+    pub base_color: Texture,
+    pub metallic: Texture,
+    pub roughness: Texture,
+    /// The strength of the reflection of the non-metallic base, where 0.5 reflects 4% head on.
+    pub specular: Texture,
+    pub clearcoat: Texture,
+    pub clearcoat_roughness: Texture,
+    /// The strength of the soft, white reflection of cloth towards grazing angles.
+    pub sheen: Texture,
+    pub transmission: Texture,
+    pub refraction_index: f64,
+    pub emission: Texture,
+}
+
+/// Reflects `wo` about the microfacet normal `wm`, failing when it would leave below the surface.
+fn reflect_microfacet(
+    distribution: &Ggx,
+    wo: &Vector3<f64>,
+    wm: &Vector3<f64>,
+    tint: Vector3<f64>,
+    frame: &Frame,
+    attenuation: &mut Vector3<f64>,
+    scattered: &mut Ray,
+) -> bool {
+    let wi = reflect(&-wo, wm);
+    if wi.z <= 0.0 {
+        return false;
+    }
+
+    scattered.direction = frame.vector_to_world(&wi);
+    attenuation.copy_from(&(tint * distribution.weight(wo, &wi)));
+    true
+}
+
+impl Surface for Principled {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Vector3<f64>,
+        scattered: &mut Ray,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        scattered.origin = record.point;
+        scattered.time = r_in.time;
+
+        let base_color = self.base_color.sample(record.u, record.v, record.point);
+        let white = Vector3::from_element(1.0);
+        let frame = Frame::new(record.point, record.normal);
+        let wo = frame.vector_to_local(&-r_in.direction.normalize());
+
+        // The clear coat is a thin varnish on top, reflecting like a 1.5 index dielectric. It is
+        // picked by its reflectance seen head on from `wo`, as `evaluate` weighs the layers below
+        // it, and the reflectance of the sampled microfacet makes up the difference.
+        let clearcoat = scalar(&self.clearcoat, record).clamp(0.0, 1.0);
+        let coat_chance = clearcoat * schlick(0.04, wo.z);
+        if coat_chance > rng.random::<f64>() {
+            let coat = Ggx::new(scalar(&self.clearcoat_roughness, record).clamp(0.0, 1.0));
+            let wm = coat.sample_normal(&wo, rng);
+            let tint = white * clearcoat * schlick(0.04, wo.dot(&wm)) / coat_chance;
+            return reflect_microfacet(&coat, &wo, &wm, tint, &frame, attenuation, scattered);
+        }
+
+        let distribution = Ggx::new(scalar(&self.roughness, record).clamp(0.0, 1.0));
+        let wm = distribution.sample_normal(&wo, rng);
+        let cos_theta = f64::min(wo.dot(&wm), 1.0);
+
+        // Metals reflect all light, tinted by their base color.
+        if scalar(&self.metallic, record) > rng.random::<f64>() {
+            let tint = Reflectance::Albedo(base_color).fresnel(cos_theta);
+            return reflect_microfacet(
+                &distribution,
+                &wo,
+                &wm,
+                tint,
+                &frame,
+                attenuation,
+                scattered,
+            );
+        }
+
+        // Glass either reflects or refracts, tinting the light passing through it.
+        if scalar(&self.transmission, record) > rng.random::<f64>() {
+            let r_index = match record.front_face {
+                true => 1.0 / self.refraction_index,
+                false => self.refraction_index,
+            };
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+            if r_index * sin_theta > 1.0 || reflectance(cos_theta, r_index) > rng.random::<f64>() {
+                return reflect_microfacet(
+                    &distribution,
+                    &wo,
+                    &wm,
+                    white,
+                    &frame,
+                    attenuation,
+                    scattered,
+                );
+            }
+
+            let wi = refract(&-wo, &wm, r_index);
+            if wi.z >= 0.0 {
+                return false;
+            }
+            scattered.direction = frame.vector_to_world(&wi);
+            attenuation.copy_from(&(base_color * distribution.weight(&wo, &wi)));
+            return true;
+        }
+
+        // Everything else is a diffuse base under a glossy dielectric reflection, picked between
+        // like the clear coat.
+        let f0 = 0.08 * scalar(&self.specular, record).clamp(0.0, 1.0);
+        let specular_chance = schlick(f0, wo.z);
+        if specular_chance > rng.random::<f64>() {
+            return reflect_microfacet(
+                &distribution,
+                &wo,
+                &wm,
+                white * schlick(f0, cos_theta) / specular_chance,
+                &frame,
+                attenuation,
+                scattered,
+            );
+        }
+
+        let mut wi = (Vector3::z() + random_normal(rng)).normalize();
+        if !wi.iter().all(|c| c.is_finite()) {
+            wi = Vector3::z();
+        }
+        let half = (wo + wi).normalize();
+        let sheen = scalar(&self.sheen, record) * (1.0 - wi.dot(&half)).clamp(0.0, 1.0).powi(5);
+
+        scattered.direction = frame.vector_to_world(&wi);
+        attenuation.copy_from(&(base_color + white * sheen));
+        true
+    }
+
+    fn emitted(&self, _: &Ray, record: &HitRecord) -> Vector3<f64> {
+        self.emission.sample(record.u, record.v, record.point)
+    }
+
+    fn evaluate(&self, r_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
+        let frame = Frame::new(record.point, record.normal);
+        let wo = frame.vector_to_local(&-r_in.direction.normalize());
+        let wi = frame.vector_to_local(&direction.normalize());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Vector3::default();
+        }
+
+        // Each layer is weighted by how often scatter picks it.
+        let base_color = self.base_color.sample(record.u, record.v, record.point);
+        let half = (wo + wi).normalize();
+        let cos_theta = wo.dot(&half);
+
+        let clearcoat = scalar(&self.clearcoat, record).clamp(0.0, 1.0);
+        let coat = Ggx::new(scalar(&self.clearcoat_roughness, record).clamp(0.0, 1.0));
+        let coat_reflection = clearcoat * schlick(0.04, cos_theta) * coat.reflection(&wo, &wi);
+        let below_coat = 1.0 - clearcoat * schlick(0.04, wo.z);
+
+        let distribution = Ggx::new(scalar(&self.roughness, record).clamp(0.0, 1.0));
+        let reflection = distribution.reflection(&wo, &wi);
+        let metal = Reflectance::Albedo(base_color).fresnel(cos_theta) * reflection;
+
+        let r_index = match record.front_face {
+            true => 1.0 / self.refraction_index,
+            false => self.refraction_index,
+        };
+        let glass = reflectance(cos_theta, r_index) * reflection;
+
+        let f0 = 0.08 * scalar(&self.specular, record).clamp(0.0, 1.0);
+        let sheen = scalar(&self.sheen, record) * (1.0 - wi.dot(&half)).clamp(0.0, 1.0).powi(5);
+        let diffuse = (base_color + Vector3::from_element(sheen)) * wi.z / PI;
+        let dielectric = Vector3::from_element(schlick(f0, cos_theta) * reflection)
+            + diffuse * (1.0 - schlick(f0, wo.z));
+
+        let metallic = scalar(&self.metallic, record).clamp(0.0, 1.0);
+        let transmission = scalar(&self.transmission, record).clamp(0.0, 1.0);
+        let base = metal * metallic
+            + (Vector3::from_element(glass) * transmission + dielectric * (1.0 - transmission))
+                * (1.0 - metallic);
+
+        Vector3::from_element(coat_reflection) + base * below_coat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::assert_scatter_matches_evaluate;
+    use crate::material::texture::SolidColor;
+
+    fn constant(value: f64) -> Texture {
+        SolidColor::texture(Vector3::from_element(value))
+    }
+
+    #[test]
+    fn test_scatter_matches_evaluate() {
+        for metallic in [0.0, 1.0] {
+            assert_scatter_matches_evaluate(&Principled {
+                base_color: constant(1.0),
+                metallic: constant(metallic),
+                roughness: constant(0.5),
+                specular: constant(0.5),
+                clearcoat: constant(0.5),
+                clearcoat_roughness: constant(0.3),
+                sheen: constant(0.0),
+                transmission: constant(0.0),
+                refraction_index: 1.5,
+                emission: constant(0.0),
+            });
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::geometry::HitRecord;
+use crate::material::Material;
+use crate::material::Surface;
+use crate::material::dielectric::Dielectric;
+use crate::material::dielectric::Dispersion;
+use crate::material::medium::Medium;
+use crate::material::microfacet::Ggx;
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use crate::ray::Ray;
+use nalgebra::Vector3;
+use rand::rngs::ThreadRng;
+use std::fmt::Debug;
+
+/// A translucent material such as skin, wax or marble, where light enters the surface and
+/// wanders around inside before leaving it again somewhere else. The surface bends light like a
+/// dielectric, and the inside is a medium light takes a random walk through, which needs the
+/// object to be closed. The medium takes the color at the point light enters through, and only
+/// its extinction is shared by the whole inside.
+#[derive(Debug, Clone)]
+pub struct Subsurface {
+    /// The surface, with a medium that scatters nothing but stops light as the whole inside does.
+    surface: Dielectric,
+    color: Texture,
+}
+
+/// The chance of light scattering rather than being absorbed at each step of a random walk that
+/// makes a thick slab of the medium look `color` overall, following van de Hulst.
+fn single_scattering_albedo(color: f64) -> f64 {
+    let color = color.clamp(0.0, 0.999);
+    let s =
+        4.09712 + 4.20863 * color - (9.59217 + 41.6808 * color + 17.7126 * color * color).sqrt();
+    1.0 - s * s
+}
+
+impl Subsurface {
+    /// Light travels about `radius` inside before scattering, separately for each color channel,
+    /// and the surface looks `color` once it has scattered many times.
+    pub fn new(
+        color: Texture,
+        radius: Vector3<f64>,
+        anisotropy: f64,
+        refraction_index: f64,
+        roughness: f64,
+    ) -> Subsurface {
+        Subsurface {
+            surface: Dielectric {
+                medium: Medium {
+                    refraction_index,
+                    absorption: radius.map(|radius| 1.0 / radius.max(1e-6)),
+                    scattering: Vector3::default(),
+                    anisotropy,
+                    priority: 0,
+                },
+                dispersion: Dispersion::None,
+                distribution: Ggx::new(roughness),
+                film: None,
+            },
+            color,
+        }
+    }
+    pub fn material(
+        color: Texture,
+        radius: Vector3<f64>,
+        anisotropy: f64,
+        refraction_index: f64,
+        roughness: f64,
+    ) -> Material {
+        Material::Subsurface(Subsurface::new(
+            color,
+            radius,
+            anisotropy,
+            refraction_index,
+            roughness,
+        ))
+    }
+
+    /// Whether `medium` is the inside of this material, wherever light entered it.
+    pub fn fills(&self, medium: &Medium) -> bool {
+        let inside = &self.surface.medium;
+        medium.refraction_index == inside.refraction_index
+            && medium.anisotropy == inside.anisotropy
+            && medium.priority == inside.priority
+            && (medium.extinction() - inside.extinction()).norm()
+                <= 1e-9 * inside.extinction().norm()
+    }
+
+    /// The surface at the hit, whose medium is the one entered there, or the one left there when
+    /// leaving.
+    fn surface(&self, r_in: &Ray, record: &HitRecord) -> Dielectric {
+        let entered = match record.front_face {
+            true => None,
+            false => r_in.media.current().filter(|medium| self.fills(medium)),
+        };
+        let medium = entered.copied().unwrap_or_else(|| {
+            let extinction = self.surface.medium.extinction();
+            let scattering = self
+                .color
+                .sample(record.u, record.v, record.point)
+                .map(single_scattering_albedo)
+                .component_mul(&extinction);
+            Medium {
+                absorption: extinction - scattering,
+                scattering,
+                ..self.surface.medium
+            }
+        });
+        Dielectric {
+            medium,
+            ..self.surface.clone()
+        }
+    }
+}
+
+impl Surface for Subsurface {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Vector3<f64>,
+        scattered: &mut Ray,
+        rng: &mut ThreadRng,
+    ) -> bool {
+        self.surface(r_in, record)
+            .scatter(r_in, record, attenuation, scattered, rng)
+    }
+
+    fn emitted(&self, r_in: &Ray, record: &HitRecord) -> Vector3<f64> {
+        self.surface(r_in, record).emitted(r_in, record)
+    }
+
+    fn evaluate(&self, r_in: &Ray, record: &HitRecord, direction: &Vector3<f64>) -> Vector3<f64> {
+        self.surface(r_in, record).evaluate(r_in, record, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::texture::Checkered;
+    use crate::material::texture::SolidColor;
+
+    fn record(u: f64, front_face: bool) -> HitRecord {
+        HitRecord {
+            u,
+            front_face,
+            ..HitRecord::default()
+        }
+    }
+
+    #[test]
+    fn test_medium_follows_entry_point() {
+        let color = Checkered::texture(
+            0.5,
+            SolidColor::texture(Vector3::from_element(0.9)),
+            SolidColor::texture(Vector3::from_element(0.2)),
+        );
+        let radius = Vector3::new(1.0, 0.5, 0.25);
+        let subsurface = Subsurface::new(color, radius, 0.0, 1.4, 0.0);
+        let ray = Ray::new(Vector3::default(), Vector3::x(), 0.0);
+
+        let even = subsurface.surface(&ray, &record(0.25, true)).medium;
+        let odd = subsurface.surface(&ray, &record(0.75, true)).medium;
+        assert!(even.scattering.x > odd.scattering.x);
+        for medium in [even, odd] {
+            assert!(subsurface.fills(&medium));
+            let extinction = radius.map(|radius| 1.0 / radius);
+            assert!((medium.extinction() - extinction).norm() < 1e-9);
+        }
+
+        // Leaving elsewhere removes the medium light entered with.
+        let mut inside = ray;
+        inside.media.push(even);
+        assert_eq!(
+            subsurface.surface(&inside, &record(0.75, false)).medium,
+            even
+        );
+
+        let other = Subsurface::new(
+            SolidColor::texture(Vector3::from_element(0.9)),
+            radius * 2.0,
+            0.0,
+            1.4,
+            0.0,
+        );
+        assert!(!other.fills(&even));
+    }
+}
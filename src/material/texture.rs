@@ -1,5 +1,6 @@
-use crate::interval::Interval;
+use crate::noise::NoiseMode;
 use crate::noise::Perlin;
+use crate::noise::worley;
 use image::Rgb32FImage;
 use nalgebra::Vector3;
 use std::fmt::Debug;
@@ -14,7 +15,18 @@ pub enum Texture {
     SolidColor(SolidColor),
     Checkered(Checkered),
     Image(Image),
+    Channel(Channel),
     Noise(Noise),
+    Wood(Wood),
+    Worley(Worley),
+    Fbm(Fbm),
+    Ridged(Ridged),
+    Gradient(Gradient),
+    ColorRamp(ColorRamp),
+    Multiply(Multiply),
+    Add(Add),
+    Lerp(Lerp),
+    Remap(Remap),
 }
 
 impl Sample for Texture {
@@ -23,7 +35,18 @@ impl Sample for Texture {
             Texture::SolidColor(texture) => texture.sample(u, v, p),
             Texture::Checkered(texture) => texture.sample(u, v, p),
             Texture::Image(texture) => texture.sample(u, v, p),
+            Texture::Channel(texture) => texture.sample(u, v, p),
             Texture::Noise(texture) => texture.sample(u, v, p),
+            Texture::Wood(texture) => texture.sample(u, v, p),
+            Texture::Worley(texture) => texture.sample(u, v, p),
+            Texture::Fbm(texture) => texture.sample(u, v, p),
+            Texture::Ridged(texture) => texture.sample(u, v, p),
+            Texture::Gradient(texture) => texture.sample(u, v, p),
+            Texture::ColorRamp(texture) => texture.sample(u, v, p),
+            Texture::Multiply(texture) => texture.sample(u, v, p),
+            Texture::Add(texture) => texture.sample(u, v, p),
+            Texture::Lerp(texture) => texture.sample(u, v, p),
+            Texture::Remap(texture) => texture.sample(u, v, p),
         }
     }
 }
@@ -47,27 +70,31 @@ impl Sample for SolidColor {
 
 #[derive(Debug, Clone)]
 pub struct Checkered {
-    pub even: Vector3<f64>,
-    pub odd: Vector3<f64>,
+    pub even: Arc<Texture>,
+    pub odd: Arc<Texture>,
     pub scale: f64,
 }
 
 impl Checkered {
-    pub fn texture(scale: f64, even: Vector3<f64>, odd: Vector3<f64>) -> Texture {
+    pub fn texture(scale: f64, even: Texture, odd: Texture) -> Texture {
         let scale = 1.0 / scale;
-        Texture::Checkered(Checkered { scale, even, odd })
+        Texture::Checkered(Checkered {
+            scale,
+            even: Arc::new(even),
+            odd: Arc::new(odd),
+        })
     }
 }
 
 impl Sample for Checkered {
-    fn sample(&self, u: f64, v: f64, _: Vector3<f64>) -> Vector3<f64> {
+    fn sample(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
         let x_int = (u * self.scale).floor() as i32;
         let y_int = (v * self.scale).floor() as i32;
 
         if (x_int + y_int) % 2 == 0 {
-            self.even
+            self.even.sample(u, v, p)
         } else {
-            self.odd
+            self.odd.sample(u, v, p)
         }
     }
 }
@@ -82,47 +109,488 @@ impl Image {
         let data = Arc::new(data);
         Texture::Image(Image { data })
     }
+
+    /// The stored value of the pixel at `u`, `v`, without any color correction.
+    pub fn texel(&self, u: f64, v: f64) -> Vector3<f64> {
+        // Wrap coordinates so meshes with tiling texture coordinates repeat the image.
+        let u = u.rem_euclid(1.0);
+        let v = v.rem_euclid(1.0);
+
+        let i = u32::min((u * self.data.width() as f64) as u32, self.data.width() - 1);
+        let j = u32::min(
+            (v * self.data.height() as f64) as u32,
+            self.data.height() - 1,
+        );
+
+        let pixel = self.data.get_pixel(i, j);
+        Vector3::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64)
+    }
+
+    /// The stored value at `u`, `v` blended between the four nearest pixels, without any color
+    /// correction.
+    pub fn bilinear(&self, u: f64, v: f64) -> Vector3<f64> {
+        let (width, height) = (self.data.width() as f64, self.data.height() as f64);
+        let x = u * width - 0.5;
+        let y = v * height - 0.5;
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+        let (u0, v0) = ((x.floor() + 0.5) / width, (y.floor() + 0.5) / height);
+        let (u1, v1) = (u0 + 1.0 / width, v0 + 1.0 / height);
+
+        let top = self.texel(u0, v0) * (1.0 - fx) + self.texel(u1, v0) * fx;
+        let bottom = self.texel(u0, v1) * (1.0 - fx) + self.texel(u1, v1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
 }
 
 impl Sample for Image {
+    /// Images are stored gamma encoded, so they are squared back into linear light, the reverse
+    /// of how renders are saved.
     fn sample(&self, u: f64, v: f64, _: Vector3<f64>) -> Vector3<f64> {
-        let interval = Interval::new(0.0, 1.0);
+        self.texel(u, v).map(|c| c * c)
+    }
+}
 
-        let u = interval.clamp(u);
-        let v = interval.clamp(v);
+/// One `channel` of an image read as a plain value rather than a color, as in the metallic and
+/// roughness values glTF packs into the channels of one image.
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub image: Image,
+    pub channel: usize,
+}
 
-        let i = (u * self.data.width() as f64) as u32;
-        let j = (v * self.data.height() as f64) as u32;
+impl Channel {
+    pub fn texture(data: Arc<Rgb32FImage>, channel: usize) -> Texture {
+        Texture::Channel(Channel {
+            image: Image { data },
+            channel,
+        })
+    }
+}
 
-        let pixel = self.data.get_pixel(i, j);
-        Vector3::new(
-            pixel[0].powf(2.0) as f64,
-            pixel[1].powf(2.0) as f64,
-            pixel[2].powf(2.0) as f64,
-        ) * 2.0
+impl Sample for Channel {
+    fn sample(&self, u: f64, v: f64, _: Vector3<f64>) -> Vector3<f64> {
+        Vector3::from_element(self.image.texel(u, v)[self.channel])
     }
 }
 
+/// Perlin noise shaped into a pattern by its `mode`, from `octaves` layers that each step up in
+/// frequency by `lacunarity` and down in amplitude by `gain`.
 #[derive(Debug, Clone)]
 pub struct Noise {
     pub perlin: Perlin,
     pub scale: f64,
-    pub turbulance: u32,
+    pub mode: NoiseMode,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+    pub colors: Colors,
 }
 
 impl Noise {
-    pub fn texture(scale: f64, turbulance: u32) -> Texture {
-        let perlin = Perlin::default();
-        Texture::Noise(Noise {
+    pub fn new(
+        perlin: Perlin,
+        scale: f64,
+        mode: NoiseMode,
+        octaves: u32,
+        lacunarity: f64,
+        gain: f64,
+        colors: Colors,
+    ) -> Self {
+        Noise {
             perlin,
             scale,
-            turbulance,
-        })
+            mode,
+            octaves,
+            lacunarity,
+            gain,
+            colors,
+        }
     }
 }
 
 impl Sample for Noise {
     fn sample(&self, _: f64, _: f64, p: Vector3<f64>) -> Vector3<f64> {
-        Vector3::from_element(1.0f64) * (self.perlin.turb(p * self.scale, self.turbulance))
+        let value = self.mode.value(
+            &self.perlin,
+            p * self.scale,
+            self.octaves,
+            self.lacunarity,
+            self.gain,
+        );
+        self.colors.at(value)
+    }
+}
+
+/// The colors a pattern blends between, `low` where it is 0 and `high` where it is 1.
+#[derive(Debug, Clone, Copy)]
+pub struct Colors {
+    pub low: Vector3<f64>,
+    pub high: Vector3<f64>,
+}
+
+impl Colors {
+    fn at(&self, t: f64) -> Vector3<f64> {
+        self.low.lerp(&self.high, t.clamp(0.0, 1.0))
+    }
+}
+
+/// Growth rings around the y axis, `scale` of them per unit, wobbled by `distortion`.
+#[derive(Debug, Clone)]
+pub struct Wood {
+    pub perlin: Perlin,
+    pub scale: f64,
+    pub distortion: f64,
+    pub colors: Colors,
+}
+
+impl Wood {
+    pub fn texture(perlin: Perlin, scale: f64, distortion: f64, colors: Colors) -> Texture {
+        Texture::Wood(Wood {
+            perlin,
+            scale,
+            distortion,
+            colors,
+        })
+    }
+}
+
+impl Sample for Wood {
+    fn sample(&self, _: f64, _: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let p = p * self.scale;
+        let radius = (p.x * p.x + p.z * p.z).sqrt() + self.distortion * self.perlin.noise(p);
+        self.colors.at(radius - radius.floor())
+    }
+}
+
+/// Which distance of cellular noise a `Worley` texture shows.
+#[derive(Debug, Clone, Copy)]
+pub enum WorleyFeature {
+    /// To the closest point, giving round cells.
+    Closest,
+    /// To the second closest point.
+    SecondClosest,
+    /// Between the closest two, which is 0 along the borders between cells.
+    Edge,
+}
+
+/// Cellular noise, with the distance to points scattered `scale` times per unit by the `seed`.
+#[derive(Debug, Clone)]
+pub struct Worley {
+    pub seed: u64,
+    pub scale: f64,
+    pub feature: WorleyFeature,
+    pub colors: Colors,
+}
+
+impl Worley {
+    pub fn texture(seed: u64, scale: f64, feature: WorleyFeature, colors: Colors) -> Texture {
+        Texture::Worley(Worley {
+            seed,
+            scale,
+            feature,
+            colors,
+        })
+    }
+}
+
+impl Sample for Worley {
+    fn sample(&self, _: f64, _: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let (closest, second) = worley(p * self.scale, self.seed);
+        self.colors.at(match self.feature {
+            WorleyFeature::Closest => closest,
+            WorleyFeature::SecondClosest => second,
+            WorleyFeature::Edge => second - closest,
+        })
+    }
+}
+
+/// Layers of Perlin noise at rising frequencies, as in clouds or rough terrain.
+#[derive(Debug, Clone)]
+pub struct Fbm {
+    pub perlin: Perlin,
+    pub scale: f64,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+    pub colors: Colors,
+}
+
+impl Fbm {
+    pub fn texture(
+        perlin: Perlin,
+        scale: f64,
+        octaves: u32,
+        lacunarity: f64,
+        gain: f64,
+        colors: Colors,
+    ) -> Texture {
+        Texture::Fbm(Fbm {
+            perlin,
+            scale,
+            octaves,
+            lacunarity,
+            gain,
+            colors,
+        })
+    }
+}
+
+impl Sample for Fbm {
+    fn sample(&self, _: f64, _: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let value = self
+            .perlin
+            .fbm(p * self.scale, self.octaves, self.lacunarity, self.gain);
+        self.colors.at(0.5 + 0.5 * value)
+    }
+}
+
+/// Sharp ridges of noise, as in mountain ranges or veins.
+#[derive(Debug, Clone)]
+pub struct Ridged {
+    pub perlin: Perlin,
+    pub scale: f64,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+    pub offset: f64,
+    pub colors: Colors,
+}
+
+impl Ridged {
+    pub fn texture(
+        perlin: Perlin,
+        scale: f64,
+        octaves: u32,
+        lacunarity: f64,
+        gain: f64,
+        offset: f64,
+        colors: Colors,
+    ) -> Texture {
+        Texture::Ridged(Ridged {
+            perlin,
+            scale,
+            octaves,
+            lacunarity,
+            gain,
+            offset,
+            colors,
+        })
+    }
+}
+
+impl Sample for Ridged {
+    fn sample(&self, _: f64, _: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let value = self.perlin.ridged(
+            p * self.scale,
+            self.octaves,
+            self.lacunarity,
+            self.gain,
+            self.offset,
+        );
+        self.colors.at(value)
+    }
+}
+
+/// A linear blend through space from `start` to `end`.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub start: Vector3<f64>,
+    pub end: Vector3<f64>,
+    pub colors: Colors,
+}
+
+impl Gradient {
+    pub fn texture(start: Vector3<f64>, end: Vector3<f64>, colors: Colors) -> Texture {
+        Texture::Gradient(Gradient { start, end, colors })
+    }
+}
+
+impl Sample for Gradient {
+    fn sample(&self, _: f64, _: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let axis = self.end - self.start;
+        let length2 = axis.norm_squared();
+        match length2 > 0.0 {
+            true => self.colors.at((p - self.start).dot(&axis) / length2),
+            false => self.colors.high,
+        }
+    }
+}
+
+/// Maps the brightness of the `input` onto colors at sorted `stops`, blending between them.
+#[derive(Debug, Clone)]
+pub struct ColorRamp {
+    pub input: Arc<Texture>,
+    pub stops: Arc<[(f64, Vector3<f64>)]>,
+}
+
+impl ColorRamp {
+    pub fn texture(input: Texture, mut stops: Vec<(f64, Vector3<f64>)>) -> Texture {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Texture::ColorRamp(ColorRamp {
+            input: Arc::new(input),
+            stops: stops.into(),
+        })
+    }
+}
+
+impl Sample for ColorRamp {
+    fn sample(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let t = self.input.sample(u, v, p).mean();
+        let after = self.stops.partition_point(|(position, _)| *position <= t);
+        match (self.stops.get(after.wrapping_sub(1)), self.stops.get(after)) {
+            (Some((a, low)), Some((b, high))) => low.lerp(high, (t - a) / (b - a)),
+            (Some((_, color)), None) | (None, Some((_, color))) => *color,
+            (None, None) => Vector3::default(),
+        }
+    }
+}
+
+/// Two textures multiplied channel by channel.
+#[derive(Debug, Clone)]
+pub struct Multiply {
+    pub a: Arc<Texture>,
+    pub b: Arc<Texture>,
+}
+
+impl Multiply {
+    pub fn texture(a: Texture, b: Texture) -> Texture {
+        Texture::Multiply(Multiply {
+            a: Arc::new(a),
+            b: Arc::new(b),
+        })
+    }
+}
+
+impl Sample for Multiply {
+    fn sample(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        self.a
+            .sample(u, v, p)
+            .component_mul(&self.b.sample(u, v, p))
+    }
+}
+
+/// Two textures added together.
+#[derive(Debug, Clone)]
+pub struct Add {
+    pub a: Arc<Texture>,
+    pub b: Arc<Texture>,
+}
+
+impl Add {
+    pub fn texture(a: Texture, b: Texture) -> Texture {
+        Texture::Add(Add {
+            a: Arc::new(a),
+            b: Arc::new(b),
+        })
+    }
+}
+
+impl Sample for Add {
+    fn sample(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        self.a.sample(u, v, p) + self.b.sample(u, v, p)
+    }
+}
+
+/// A blend from `a` to `b` by the brightness of `factor`.
+#[derive(Debug, Clone)]
+pub struct Lerp {
+    pub a: Arc<Texture>,
+    pub b: Arc<Texture>,
+    pub factor: Arc<Texture>,
+}
+
+impl Lerp {
+    pub fn texture(a: Texture, b: Texture, factor: Texture) -> Texture {
+        Texture::Lerp(Lerp {
+            a: Arc::new(a),
+            b: Arc::new(b),
+            factor: Arc::new(factor),
+        })
+    }
+}
+
+impl Sample for Lerp {
+    fn sample(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let factor = self.factor.sample(u, v, p).mean().clamp(0.0, 1.0);
+        self.a.sample(u, v, p).lerp(&self.b.sample(u, v, p), factor)
+    }
+}
+
+/// The `input` with each channel moved linearly from the range `from` onto the range `to`,
+/// clamping values outside of it.
+#[derive(Debug, Clone)]
+pub struct Remap {
+    pub input: Arc<Texture>,
+    pub from: [f64; 2],
+    pub to: [f64; 2],
+}
+
+impl Remap {
+    pub fn texture(input: Texture, from: [f64; 2], to: [f64; 2]) -> Texture {
+        Texture::Remap(Remap {
+            input: Arc::new(input),
+            from,
+            to,
+        })
+    }
+}
+
+impl Sample for Remap {
+    fn sample(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let [from_min, from_max] = self.from;
+        let [to_min, to_max] = self.to;
+        self.input.sample(u, v, p).map(|value| {
+            // An empty range splits the values in two, as the clamping does around a short one.
+            let t = match from_max != from_min {
+                true => ((value - from_min) / (from_max - from_min)).clamp(0.0, 1.0),
+                false => f64::from(value >= from_min),
+            };
+            to_min + t * (to_max - to_min)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(value: f64) -> Texture {
+        SolidColor::texture(Vector3::from_element(value))
+    }
+
+    #[test]
+    fn test_color_ramp_interpolates_between_stops() {
+        let stops = vec![
+            (0.8, Vector3::new(0.0, 0.0, 1.0)),
+            (0.2, Vector3::new(1.0, 0.0, 0.0)),
+            (0.4, Vector3::new(0.0, 1.0, 0.0)),
+        ];
+        let ramp = |t: f64| {
+            ColorRamp::texture(gray(t), stops.clone()).sample(0.0, 0.0, Vector3::default())
+        };
+
+        assert!((ramp(0.3) - Vector3::new(0.5, 0.5, 0.0)).norm() < 1e-12);
+        assert!((ramp(0.7) - Vector3::new(0.0, 0.25, 0.75)).norm() < 1e-12);
+        assert!((ramp(0.4) - Vector3::new(0.0, 1.0, 0.0)).norm() < 1e-12);
+        // Past either end the closest stop is held.
+        assert_eq!(ramp(0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(ramp(1.0), Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_remap() {
+        let remap = |value: f64, from: [f64; 2]| {
+            Remap::texture(gray(value), from, [2.0, 4.0])
+                .sample(0.0, 0.0, Vector3::default())
+                .x
+        };
+        assert!((remap(0.25, [0.0, 0.5]) - 3.0).abs() < 1e-12);
+        assert_eq!(remap(-1.0, [0.0, 0.5]), 2.0);
+        assert_eq!(remap(1.0, [0.0, 0.5]), 4.0);
+
+        // An empty range is a step at its value.
+        assert_eq!(remap(0.4, [0.5, 0.5]), 2.0);
+        assert_eq!(remap(0.5, [0.5, 0.5]), 4.0);
+        assert_eq!(remap(0.6, [0.5, 0.5]), 4.0);
     }
 }
@@ -0,0 +1,124 @@
+use crate::geometry::HitRecord;
+use crate::material::texture::Sample;
+use crate::material::texture::Texture;
+use nalgebra::Complex;
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+
+/// Wavelengths in nanometers standing in for the red, green and blue channels when not
+/// rendering spectrally.
+const RGB_WAVELENGTHS: [f64; 3] = [630.0, 532.0, 465.0];
+
+/// A transparent film a few hundred nanometers thick on top of a surface, like soap or oil,
+/// where light reflected off the top and bottom of the film interferes into shifting rainbow
+/// colors. The `thickness` in nanometers is scaled by the brightness of the optional `texture`.
+#[derive(Debug, Clone)]
+pub struct ThinFilm {
+    pub thickness: f64,
+    pub texture: Option<Texture>,
+    pub refraction_index: f64,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f64, texture: Option<Texture>, refraction_index: f64) -> ThinFilm {
+        ThinFilm {
+            thickness,
+            texture,
+            refraction_index,
+        }
+    }
+
+    /// The share of light reflected in each channel, arriving through a medium with the
+    /// refraction index `outside` at an angle with a cosine of `cos_theta`, onto a film covering
+    /// a `substrate` with a complex refraction index per channel. Spectral rays are reflected
+    /// at their own `wavelength` with every channel.
+    pub fn reflectance(
+        &self,
+        record: &HitRecord,
+        wavelength: Option<f64>,
+        cos_theta: f64,
+        outside: f64,
+        substrate: &[Complex<f64>; 3],
+    ) -> Vector3<f64> {
+        let scale = self.texture.as_ref().map_or(1.0, |texture| {
+            texture.sample(record.u, record.v, record.point).mean()
+        });
+        let thickness = self.thickness * scale.max(0.0);
+        Vector3::from_fn(|channel, _| {
+            airy(
+                cos_theta,
+                outside,
+                self.refraction_index,
+                substrate[channel],
+                thickness,
+                wavelength.unwrap_or(RGB_WAVELENGTHS[channel]),
+            )
+        })
+    }
+}
+
+/// The reflectance of unpolarized light off a film of index `film` and `thickness` over a
+/// `substrate`, summing every reflection inside the film with its phase.
+fn airy(
+    cos_theta: f64,
+    outside: f64,
+    film: f64,
+    substrate: Complex<f64>,
+    thickness: f64,
+    wavelength: f64,
+) -> f64 {
+    let cos_theta = cos_theta.clamp(0.0, 1.0);
+    let sin2 = Complex::from(outside * outside * (1.0 - cos_theta * cos_theta));
+
+    // The component of the wave vector normal to the surface in each layer, as a multiple of
+    // that of a vacuum, taking the root that decays into absorbing layers.
+    let n = [Complex::from(outside), Complex::from(film), substrate];
+    let q = n.map(|n| (n * n - sin2).sqrt());
+
+    let s = |i: usize, j: usize| (q[i] - q[j]) / (q[i] + q[j]);
+    let p = |i: usize, j: usize| {
+        let (ni2, nj2) = (n[i] * n[i], n[j] * n[j]);
+        (nj2 * q[i] - ni2 * q[j]) / (nj2 * q[i] + ni2 * q[j])
+    };
+
+    let shift = (Complex::i() * 4.0 * PI * thickness / wavelength * q[1]).exp();
+    let total = |r12: Complex<f64>, r23: Complex<f64>| {
+        ((r12 + r23 * shift) / (Complex::from(1.0) + r12 * r23 * shift)).norm_sqr()
+    };
+    0.5 * (total(s(0, 1), s(1, 2)) + total(p(0, 1), p(1, 2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::microfacet::fresnel_conductor;
+
+    #[test]
+    fn test_airy_without_film_matches_fresnel() {
+        // A film too thin to matter, or no different from the air around it, leaves the
+        // reflectance of the metal underneath.
+        let (eta, k) = (0.2, 3.9);
+        for cos_theta in [1.0, 0.8, 0.5, 0.2] {
+            let expected = fresnel_conductor(cos_theta, eta, k);
+            let substrate = Complex::new(eta, k);
+            let bare = airy(cos_theta, 1.0, 1.33, substrate, 0.0, 550.0);
+            let invisible = airy(cos_theta, 1.0, 1.0, substrate, 400.0, 550.0);
+            assert!((bare - expected).abs() < 1e-9, "{bare} != {expected}");
+            assert!(
+                (invisible - expected).abs() < 1e-9,
+                "{invisible} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_airy_quarter_wave_coating() {
+        // A quarter wave film with an index of the root of the glass underneath cancels the
+        // reflection head on, as on an anti-reflective coating.
+        let glass: f64 = 1.5;
+        let film = glass.sqrt();
+        let thickness = 550.0 / (4.0 * film);
+        let reflectance = airy(1.0, 1.0, film, Complex::from(glass), thickness, 550.0);
+        assert!(reflectance < 1e-9, "{reflectance}");
+    }
+}
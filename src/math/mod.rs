@@ -1,6 +1,7 @@
 use nalgebra::Vector2;
 use nalgebra::Vector3;
 use rand::prelude::*;
+use std::f64::consts::PI;
 
 pub fn random_vector<T: Rng>(rng: &mut T) -> Vector3<f64> {
     Vector3::new(
@@ -53,6 +54,117 @@ pub fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
     r1 + (1.0 - r1) * (1.0 - cosine).powf(5.0)
 }
 
+/// Samples the cosine of the angle between the incoming and scattered directions from the
+/// Henyey-Greenstein phase function, which scatters forwards for positive `g` and backwards for
+/// negative `g`.
+pub fn sample_henyey_greenstein<T: Rng>(g: f64, rng: &mut T) -> f64 {
+    let xi = rng.random::<f64>();
+    if g.abs() < 1e-3 {
+        return 1.0 - 2.0 * xi;
+    }
+    let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+    ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+}
+
+/// The Henyey-Greenstein phase function for the cosine of the angle between the incoming and
+/// scattered directions, as a density over the sphere of directions.
+pub fn henyey_greenstein(g: f64, cos_theta: f64) -> f64 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+}
+
+/// The real roots of `a x^2 + b x + c`, in ascending order.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        return match b.abs() < 1e-12 {
+            true => vec![],
+            false => vec![-c / b],
+        };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+
+    // Avoids the cancellation of subtracting nearly equal values.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (x0, x1) = match q == 0.0 {
+        true => (0.0, 0.0),
+        false => (q / a, c / q),
+    };
+    match x0 < x1 {
+        true => vec![x0, x1],
+        false => vec![x1, x0],
+    }
+}
+
+/// The real roots of `x^3 + a x^2 + b x + c`, in ascending order.
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+
+    if r * r < q * q * q {
+        let theta = (r / (q * q * q).sqrt()).clamp(-1.0, 1.0).acos();
+        let scale = -2.0 * q.sqrt();
+        let mut roots = [0.0, 2.0, -2.0].map(|offset: f64| {
+            scale * ((theta + offset * std::f64::consts::PI) / 3.0).cos() - a / 3.0
+        });
+        roots.sort_by(f64::total_cmp);
+        return roots.to_vec();
+    }
+
+    let big_a = -r.signum() * (r.abs() + (r * r - q * q * q).sqrt()).cbrt();
+    let big_b = if big_a == 0.0 { 0.0 } else { q / big_a };
+    vec![big_a + big_b - a / 3.0]
+}
+
+/// The real roots of `x^4 + a x^3 + b x^2 + c x + d`, in ascending order.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // Substituting x = y - a / 4 gives the depressed quartic y^4 + p y^2 + q y + r.
+    let p = b - 3.0 * a * a / 8.0;
+    let q = c - a * b / 2.0 + a * a * a / 8.0;
+    let r = d - a * c / 4.0 + a * a * b / 16.0 - 3.0 * a * a * a * a / 256.0;
+
+    let mut roots: Vec<f64> = if q.abs() < 1e-12 {
+        solve_quadratic(1.0, p, r)
+            .into_iter()
+            .filter(|z| *z >= 0.0)
+            .flat_map(|z| [-z.sqrt(), z.sqrt()])
+            .collect()
+    } else {
+        // Ferrari's method splits the quartic into two quadratics using a positive root of its
+        // resolvent cubic.
+        let m = solve_cubic(p, p * p / 4.0 - r, -q * q / 8.0)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 {
+            return vec![];
+        }
+        let s = (2.0 * m).sqrt();
+
+        let mut roots = solve_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s));
+        roots.extend(solve_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s)));
+        roots
+    };
+
+    // Polish the roots against the original polynomial, as the substitutions lose precision.
+    for root in roots.iter_mut() {
+        *root -= a / 4.0;
+        for _ in 0..2 {
+            let x = *root;
+            let value = (((x + a) * x + b) * x + c) * x + d;
+            let slope = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+            if slope.abs() > f64::EPSILON {
+                *root = x - value / slope;
+            }
+        }
+    }
+
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,4 +255,71 @@ mod tests {
         let reflectance_value = reflectance(cosine, refraction_index);
         assert_eq!(reflectance_value, 0.07);
     }
+
+    #[test]
+    fn test_sample_henyey_greenstein() {
+        // The mean cosine of the phase function is its asymmetry.
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        for g in [-0.7, 0.0, 0.3, 0.9] {
+            let mean = (0..100000)
+                .map(|_| sample_henyey_greenstein(g, &mut rng))
+                .sum::<f64>()
+                / 100000.0;
+            assert!((mean - g).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_henyey_greenstein() {
+        // The phase function integrates to one over the sphere.
+        for g in [-0.7, 0.0, 0.3, 0.9] {
+            let steps = 100000;
+            let integral = (0..steps)
+                .map(|i| -1.0 + 2.0 * (i as f64 + 0.5) / steps as f64)
+                .map(|cos_theta| henyey_greenstein(g, cos_theta) * 2.0 * PI * 2.0 / steps as f64)
+                .sum::<f64>();
+            assert!((integral - 1.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_solve_quadratic() {
+        assert_eq!(solve_quadratic(1.0, -3.0, 2.0), vec![1.0, 2.0]);
+        assert_eq!(solve_quadratic(0.0, 2.0, -4.0), vec![2.0]);
+        assert!(solve_quadratic(1.0, 0.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_solve_cubic() {
+        // (x - 1)(x - 2)(x - 3)
+        let roots = solve_cubic(-6.0, 11.0, -6.0);
+        assert_eq!(roots.len(), 3);
+        for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+
+        // (x - 2)(x^2 + 1)
+        let roots = solve_cubic(-2.0, 1.0, -2.0);
+        assert_eq!(roots.len(), 1);
+        assert!((roots[0] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_solve_quartic() {
+        // (x + 3)(x + 1)(x - 2)(x - 5)
+        let roots = solve_quartic(-3.0, -15.0, 19.0, 30.0);
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip([-3.0, -1.0, 2.0, 5.0]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+
+        // (x^2 - 4)(x^2 + 1)
+        let roots = solve_quartic(0.0, -3.0, 0.0, -4.0);
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0] + 2.0).abs() < 1e-9);
+        assert!((roots[1] - 2.0).abs() < 1e-9);
+
+        // x^4 + 1
+        assert!(solve_quartic(0.0, 0.0, 0.0, 1.0).is_empty());
+    }
 }
@@ -1,26 +1,28 @@
 use itertools::iproduct;
 use nalgebra::Vector3;
 use rand::prelude::*;
-use rand::rngs::ThreadRng;
+use rand_chacha::ChaCha8Rng;
+use std::sync::Arc;
 
 const PERLIN_POINT_COUNT: usize = 256;
 
+/// Gradient noise, which is the same for the same `seed`. Every cell of the lattice is hashed
+/// with the seed to pick one of its random gradients, so the noise never repeats.
 #[derive(Debug, Clone)]
 pub struct Perlin {
-    randvec: Box<[Vector3<f64>; PERLIN_POINT_COUNT]>,
-    perm_x: Box<[usize; PERLIN_POINT_COUNT]>,
-    perm_y: Box<[usize; PERLIN_POINT_COUNT]>,
-    perm_z: Box<[usize; PERLIN_POINT_COUNT]>,
+    seed: u64,
+    randvec: Arc<[Vector3<f64>; PERLIN_POINT_COUNT]>,
 }
 
 impl Default for Perlin {
     fn default() -> Self {
-        Perlin::new(&mut rand::rng())
+        Perlin::new(rand::random())
     }
 }
 
 impl Perlin {
-    pub fn new(rng: &mut ThreadRng) -> Self {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
         let mut randvec = [Vector3::<f64>::default(); PERLIN_POINT_COUNT];
         for vec in randvec.iter_mut() {
             *vec = Vector3::new(
@@ -31,20 +33,9 @@ impl Perlin {
             .normalize();
         }
 
-        let mut perm_x = Box::new([0; PERLIN_POINT_COUNT]);
-        Perlin::perlin_generate_perm(&mut perm_x, rng);
-
-        let mut perm_y = Box::new([0; PERLIN_POINT_COUNT]);
-        Perlin::perlin_generate_perm(&mut perm_y, rng);
-
-        let mut perm_z = Box::new([0; PERLIN_POINT_COUNT]);
-        Perlin::perlin_generate_perm(&mut perm_z, rng);
-
         Perlin {
-            randvec: Box::new(randvec),
-            perm_x,
-            perm_y,
-            perm_z,
+            seed,
+            randvec: Arc::new(randvec),
         }
     }
 
@@ -53,45 +44,85 @@ impl Perlin {
         let v = point.y - point.y.floor();
         let w = point.z - point.z.floor();
 
-        let i = (point.x.floor()) as i32;
-        let j = (point.y.floor()) as i32;
-        let k = (point.z.floor()) as i32;
+        let i = (point.x.floor()) as i64;
+        let j = (point.y.floor()) as i64;
+        let k = (point.z.floor()) as i64;
         let mut c = [[[Vector3::<f64>::default(); 2]; 2]; 2];
 
         iproduct!(0..2, 0..2, 0..2).for_each(|(di, dj, dk)| {
-            let xi = ((i + di) & 255) as usize;
-            let yi = ((j + dj) & 255) as usize;
-            let zi = ((k + dk) & 255) as usize;
+            let hash = hash_cell(i + di, j + dj, k + dk, self.seed);
             c[di as usize][dj as usize][dk as usize] =
-                self.randvec[self.perm_x[xi] ^ self.perm_y[yi] ^ self.perm_z[zi]];
+                self.randvec[hash as usize % PERLIN_POINT_COUNT];
         });
 
         Perlin::perlin_interp(&c, u, v, w)
     }
 
-    pub fn perlin_generate_perm(p: &mut [usize; PERLIN_POINT_COUNT], rng: &mut ThreadRng) {
-        p.iter_mut().enumerate().for_each(|(i, p_i)| *p_i = i);
-        Perlin::permute(p, PERLIN_POINT_COUNT, rng);
-    }
-
-    pub fn permute(p: &mut [usize; PERLIN_POINT_COUNT], n: usize, rng: &mut ThreadRng) {
-        (1..n).rev().for_each(|i| p.swap(i, rng.random_range(0..i)));
-    }
-
-    pub fn turb(&self, point: Vector3<f64>, depth: u32) -> f64 {
+    /// Turbulence, the size of the sum of `octaves` layers of noise that each step up in
+    /// frequency by `lacunarity` and down in amplitude by `gain`.
+    pub fn turb(&self, point: Vector3<f64>, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
         let mut temp_p = point;
         let mut weight = 1.0;
-        (0..depth)
+        (0..octaves)
             .map(|_| {
                 let val = weight * self.noise(temp_p);
-                weight *= 0.5;
-                temp_p *= 2.0;
+                weight *= gain;
+                temp_p *= lacunarity;
                 val
             })
             .sum::<f64>()
             .abs()
     }
 
+    /// Fractal Brownian motion, summing `octaves` layers of noise that each step up in frequency
+    /// by `lacunarity` and down in amplitude by `gain`. Normalized to about -1 to 1.
+    pub fn fbm(&self, point: Vector3<f64>, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        let mut point = point;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut sum = 0.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(point);
+            total += amplitude;
+            amplitude *= gain;
+            point *= lacunarity;
+        }
+        match total > 0.0 {
+            true => sum / total,
+            false => 0.0,
+        }
+    }
+
+    /// Musgrave's ridged multifractal, which folds the noise of each octave into sharp ridges
+    /// around `offset` and lets the ridges of one octave gate the detail of the next by `gain`.
+    /// Normalized to 0 to 1.
+    pub fn ridged(
+        &self,
+        point: Vector3<f64>,
+        octaves: u32,
+        lacunarity: f64,
+        gain: f64,
+        offset: f64,
+    ) -> f64 {
+        let mut point = point;
+        let mut weight = 1.0;
+        let mut frequency = 1.0;
+        let mut total = 0.0;
+        let mut sum = 0.0;
+        for _ in 0..octaves {
+            let signal = (offset - self.noise(point).abs()).powi(2) * weight;
+            weight = (signal * gain).clamp(0.0, 1.0);
+            sum += signal / frequency;
+            total += offset * offset / frequency;
+            frequency *= lacunarity;
+            point *= lacunarity;
+        }
+        match total > 0.0 {
+            true => (sum / total).clamp(0.0, 1.0),
+            false => 0.0,
+        }
+    }
+
     pub fn perlin_interp(c: &[[[Vector3<f64>; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
         let uu = u * u * (3.0 - 2.0 * u);
         let vv = v * v * (3.0 - 2.0 * v);
@@ -113,3 +144,124 @@ impl Perlin {
             .sum()
     }
 }
+
+/// How a `Perlin` noise is turned into a pattern between 0 and 1.
+#[derive(Debug, Clone, Copy)]
+pub enum NoiseMode {
+    /// Fractal noise as it is.
+    Raw,
+    /// The size of fractal noise, folded up at its zeros.
+    Turbulence,
+    /// Stripes along the z axis, bent by turbulence into the veins of marble.
+    Marble,
+    /// Fractal noise passed through a sine wave, making rippling bands.
+    SineWarped,
+}
+
+impl NoiseMode {
+    pub fn value(
+        &self,
+        perlin: &Perlin,
+        point: Vector3<f64>,
+        octaves: u32,
+        lacunarity: f64,
+        gain: f64,
+    ) -> f64 {
+        match self {
+            NoiseMode::Raw => 0.5 * (1.0 + perlin.fbm(point, octaves, lacunarity, gain)),
+            NoiseMode::Turbulence => perlin.turb(point, octaves, lacunarity, gain),
+            NoiseMode::Marble => {
+                let turbulence = perlin.turb(point, octaves, lacunarity, gain);
+                0.5 * (1.0 + (point.z + 10.0 * turbulence).sin())
+            }
+            NoiseMode::SineWarped => {
+                let fbm = perlin.fbm(point, octaves, lacunarity, gain);
+                0.5 * (1.0 + (10.0 * fbm).sin())
+            }
+        }
+    }
+}
+
+/// A well mixed hash of an integer lattice cell, different for every `seed`.
+fn hash_cell(i: i64, j: i64, k: i64, seed: u64) -> u64 {
+    let mut h = (i as u64).wrapping_mul(0x9e3779b97f4a7c15)
+        ^ (j as u64).wrapping_mul(0xc2b2ae3d27d4eb4f)
+        ^ (k as u64).wrapping_mul(0x165667b19e3779f9)
+        ^ seed.wrapping_mul(0xd6e8feb86659fd93);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51afd7ed558ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+    h ^ (h >> 33)
+}
+
+/// Worley's cellular noise, with one point scattered in every unit cell of space, placed by the
+/// `seed`. Gives the distances from `point` to the closest and second closest of them.
+pub fn worley(point: Vector3<f64>, seed: u64) -> (f64, f64) {
+    let cell = point.map(|x| x.floor() as i64);
+    let mut closest = (f64::INFINITY, f64::INFINITY);
+    iproduct!(-1..=1, -1..=1, -1..=1).for_each(|(di, dj, dk)| {
+        let (i, j, k) = (cell.x + di, cell.y + dj, cell.z + dk);
+        let h = hash_cell(i, j, k, seed);
+        let offset = Vector3::new(h & 0x1fffff, (h >> 21) & 0x1fffff, (h >> 42) & 0x1fffff)
+            .map(|bits| bits as f64 / (1 << 21) as f64);
+        let feature = Vector3::new(i as f64, j as f64, k as f64) + offset;
+        let distance = (feature - point).norm();
+        if distance < closest.0 {
+            closest = (distance, closest.0);
+        } else if distance < closest.1 {
+            closest.1 = distance;
+        }
+    });
+    closest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_worley_orders_distances() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        for _ in 0..1000 {
+            let point = random_point(&mut rng) * 10.0;
+            let (closest, second) = worley(point, 0);
+            assert!(closest <= second);
+            // Every cell holds a point, so one is always within the diagonal of a cell.
+            assert!(closest <= 3.0f64.sqrt());
+        }
+    }
+
+    fn random_point(rng: &mut impl Rng) -> Vector3<f64> {
+        Vector3::new(rng.random(), rng.random(), rng.random())
+    }
+
+    #[test]
+    fn test_perlin_seeded() {
+        let point = Vector3::new(1.3, -4.7, 2.2);
+        let a = Perlin::new(42).noise(point);
+        assert_eq!(a, Perlin::new(42).noise(point));
+        assert_ne!(a, Perlin::new(43).noise(point));
+    }
+
+    #[test]
+    fn test_perlin_does_not_repeat() {
+        let perlin = Perlin::new(7);
+        let point = Vector3::new(0.3, 0.6, 0.9);
+        let shifted = point + Vector3::new(256.0, 0.0, 0.0);
+        assert_ne!(perlin.noise(point), perlin.noise(shifted));
+    }
+
+    #[test]
+    fn test_ridged_is_normalized() {
+        let perlin = Perlin::new(7);
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        for _ in 0..1000 {
+            let point = random_point(&mut rng) * 10.0;
+            let value = perlin.ridged(point, 6, 2.0, 2.0, 1.0);
+            assert!((0.0..=1.0).contains(&value));
+        }
+    }
+}
@@ -1,10 +1,18 @@
+use crate::material::medium::MediumStack;
 use nalgebra::Vector3;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy)]
 pub struct Ray {
     pub origin: Vector3<f64>,
     pub direction: Vector3<f64>,
     pub time: f64,
+    /// The dielectrics the ray is travelling through.
+    pub media: MediumStack,
+    /// The wavelength of light carried by the ray in nanometers, when rendering spectrally.
+    pub wavelength: Option<f64>,
+    /// How likely each color channel was to pick the distances the path travelled through
+    /// scattering media, relative to the others.
+    pub channel_pdfs: Vector3<f64>,
 }
 
 impl Ray {
@@ -13,6 +21,9 @@ impl Ray {
             origin,
             direction,
             time,
+            media: MediumStack::default(),
+            wavelength: None,
+            channel_pdfs: Vector3::from_element(1.0),
         }
     }
 
@@ -21,6 +32,12 @@ impl Ray {
     }
 }
 
+impl Default for Ray {
+    fn default() -> Self {
+        Ray::new(Vector3::default(), Vector3::default(), 0.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,177 @@
+use nalgebra::Matrix3;
+use nalgebra::Vector3;
+use rand::prelude::*;
+
+/// The range of visible wavelengths, in nanometers.
+pub const MIN_WAVELENGTH: f64 = 380.0;
+pub const MAX_WAVELENGTH: f64 = 780.0;
+
+/// Wavelengths splitting the spectrum into the bands colors are spread over, from red to blue.
+const BANDS: [f64; 2] = [585.0, 490.0];
+
+/// CIE XYZ to linear sRGB.
+const XYZ_TO_RGB: Matrix3<f64> = Matrix3::new(
+    3.2404542, -1.5371385, -0.4985314, //
+    -0.9692660, 1.8760108, 0.0415560, //
+    0.0556434, -0.2040259, 1.0572252,
+);
+
+fn lobe(wavelength: f64, mean: f64, below: f64, above: f64) -> f64 {
+    let width = if wavelength < mean { below } else { above };
+    (-0.5 * ((wavelength - mean) / width).powi(2)).exp()
+}
+
+/// The CIE 1931 color matching functions.
+///
+/// Wyman et al., "Simple Analytic Approximations to the CIE XYZ Color Matching Functions"
+pub fn cie_xyz(wavelength: f64) -> Vector3<f64> {
+    Vector3::new(
+        1.056 * lobe(wavelength, 599.8, 37.9, 31.0) + 0.362 * lobe(wavelength, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(wavelength, 501.1, 20.4, 26.2),
+        0.821 * lobe(wavelength, 568.8, 46.9, 40.5) + 0.286 * lobe(wavelength, 530.9, 16.3, 31.1),
+        1.217 * lobe(wavelength, 437.0, 11.8, 36.0) + 0.681 * lobe(wavelength, 459.0, 26.0, 13.8),
+    )
+}
+
+/// The spectral radiance of a black body at `kelvin`, for a `wavelength` in nanometers.
+fn planck(wavelength: f64, kelvin: f64) -> f64 {
+    const H: f64 = 6.62607015e-34;
+    const C: f64 = 299792458.0;
+    const K: f64 = 1.380649e-23;
+
+    let wavelength = wavelength * 1e-9;
+    2.0 * H * C * C / (wavelength.powi(5) * ((H * C / (wavelength * K * kelvin)).exp() - 1.0))
+}
+
+/// The linear sRGB color of a black body glowing at `kelvin`, scaled to a luminance of one.
+pub fn blackbody(kelvin: f64) -> Vector3<f64> {
+    let xyz = (MIN_WAVELENGTH as u32..MAX_WAVELENGTH as u32)
+        .map(|w| w as f64 + 0.5)
+        .map(|w| cie_xyz(w) * planck(w, kelvin))
+        .sum::<Vector3<f64>>();
+    (XYZ_TO_RGB * xyz / xyz.y).map(|c| c.max(0.0))
+}
+
+fn band(wavelength: f64) -> usize {
+    match wavelength {
+        w if w >= BANDS[0] => 0,
+        w if w >= BANDS[1] => 1,
+        _ => 2,
+    }
+}
+
+/// Converts between colors and spectra. Each color is spread over the bands of the spectrum so
+/// that it turns back into the same color, with white becoming a flat spectrum.
+#[derive(Debug, Clone, Copy)]
+pub struct Spectrum {
+    white: Vector3<f64>,
+    to_bands: Matrix3<f64>,
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Spectrum::new()
+    }
+}
+
+impl Spectrum {
+    pub fn new() -> Self {
+        let wavelengths = (MIN_WAVELENGTH as u32..MAX_WAVELENGTH as u32).map(|w| w as f64 + 0.5);
+
+        let mut white = Vector3::zeros();
+        let mut bands = Matrix3::zeros();
+        for wavelength in wavelengths {
+            let rgb = XYZ_TO_RGB * cie_xyz(wavelength);
+            white += rgb;
+            let mut column = bands.column_mut(band(wavelength));
+            column += rgb;
+        }
+
+        // Colors are balanced so a flat spectrum is white rather than slightly pink.
+        for mut column in bands.column_iter_mut() {
+            column.component_div_assign(&white);
+        }
+        let to_bands = bands
+            .try_inverse()
+            .expect("Spectral bands must be independent");
+
+        Spectrum { white, to_bands }
+    }
+
+    /// Picks the wavelength for the `index`th of `count` samples, spreading them evenly across
+    /// the spectrum.
+    pub fn sample_wavelength<T: Rng>(index: u32, count: u32, rng: &mut T) -> f64 {
+        let fraction = (index as f64 + rng.random::<f64>()) / count as f64;
+        MIN_WAVELENGTH + fraction * (MAX_WAVELENGTH - MIN_WAVELENGTH)
+    }
+
+    /// The value at `wavelength` of the spectrum with the color `rgb`.
+    pub fn sample(&self, rgb: &Vector3<f64>, wavelength: f64) -> f64 {
+        (self.to_bands * rgb)[band(wavelength)]
+    }
+
+    /// The color of `value` at `wavelength`, weighted for wavelengths picked evenly across the
+    /// spectrum.
+    pub fn color(&self, value: f64, wavelength: f64) -> Vector3<f64> {
+        (XYZ_TO_RGB * cie_xyz(wavelength)).component_div(&self.white)
+            * value
+            * (MAX_WAVELENGTH - MIN_WAVELENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha8Rng;
+
+    fn round_trip(spectrum: &Spectrum, rgb: &Vector3<f64>) -> Vector3<f64> {
+        let count = (MAX_WAVELENGTH - MIN_WAVELENGTH) as u32;
+        (0..count)
+            .map(|i| MIN_WAVELENGTH + i as f64 + 0.5)
+            .map(|w| spectrum.color(spectrum.sample(rgb, w), w))
+            .sum::<Vector3<f64>>()
+            / count as f64
+    }
+
+    #[test]
+    fn test_spectrum_white_is_flat() {
+        let spectrum = Spectrum::new();
+        let white = Vector3::new(1.0, 1.0, 1.0);
+        for wavelength in [400.0, 500.0, 600.0, 700.0] {
+            assert!((spectrum.sample(&white, wavelength) - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_spectrum_round_trip() {
+        let spectrum = Spectrum::new();
+        for rgb in [
+            Vector3::new(1.0, 1.0, 1.0),
+            Vector3::new(0.8, 0.2, 0.1),
+            Vector3::new(0.1, 0.5, 0.9),
+        ] {
+            assert!((round_trip(&spectrum, &rgb) - rgb).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_blackbody() {
+        // Daylight is close to white, candle light is red and a blue sky is blue.
+        let daylight = blackbody(6500.0);
+        assert!((daylight - Vector3::from_element(1.0)).amax() < 0.1);
+        let candle = blackbody(1900.0);
+        assert!(candle.x > candle.y && candle.y > candle.z);
+        let sky = blackbody(15000.0);
+        assert!(sky.z > sky.x);
+    }
+
+    #[test]
+    fn test_sample_wavelength() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0xdeadbeef);
+        for index in 0..4 {
+            let wavelength = Spectrum::sample_wavelength(index, 4, &mut rng);
+            let stratum = MIN_WAVELENGTH + index as f64 * 100.0;
+            assert!((stratum..stratum + 100.0).contains(&wavelength));
+        }
+    }
+}